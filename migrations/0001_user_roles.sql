@@ -0,0 +1,25 @@
+-- Multiple roles per user
+--
+-- Replaces the single nullable users.role_id FK with a user_roles association
+-- and carries every existing assignment over before dropping the old column.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS user_roles (
+    user_id    INTEGER     NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role_id    INTEGER     NOT NULL REFERENCES roles (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, role_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_user_roles_role_id ON user_roles (role_id);
+
+INSERT INTO user_roles (user_id, role_id)
+SELECT id, role_id
+FROM users
+WHERE role_id IS NOT NULL
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE users DROP COLUMN IF EXISTS role_id;
+
+COMMIT;
@@ -0,0 +1,16 @@
+-- Role inheritance hierarchy
+--
+-- A role may declare a parent role and inherits all of its permissions.
+-- Cycles longer than a self-reference are rejected by the application.
+
+BEGIN;
+
+ALTER TABLE roles
+    ADD COLUMN IF NOT EXISTS parent_id INTEGER REFERENCES roles (id) ON DELETE SET NULL;
+
+ALTER TABLE roles
+    ADD CONSTRAINT roles_parent_not_self CHECK (parent_id IS NULL OR parent_id <> id);
+
+CREATE INDEX IF NOT EXISTS idx_roles_parent_id ON roles (parent_id);
+
+COMMIT;
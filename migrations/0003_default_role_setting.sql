@@ -0,0 +1,20 @@
+-- Configurable default signup role
+--
+-- Registration used to hard-code role 2; the role now comes from system_settings.
+
+INSERT INTO system_settings (key, "group", name, value, default_value, type, editable, sensitive, description, sort, created_at, updated_at)
+VALUES (
+    'auth.default_role_id',
+    'auth',
+    'Default signup role',
+    '2'::jsonb,
+    '2'::jsonb,
+    'number',
+    TRUE,
+    FALSE,
+    'Role ID assigned to users who register themselves',
+    0,
+    now(),
+    now()
+)
+ON CONFLICT (key) DO NOTHING;
@@ -0,0 +1,7 @@
+-- Forced password change
+--
+-- Set when an administrator resets a password; the user must pick a new one
+-- before any other authenticated request is accepted.
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS password_change_required BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,20 @@
+-- Pending email changes
+--
+-- A change only takes effect once the new address proves ownership by
+-- presenting the emailed token. Only the SHA-256 hash of the token is stored.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS email_change_requests (
+    id           SERIAL       PRIMARY KEY,
+    user_id      INTEGER      NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    new_email    VARCHAR(255) NOT NULL,
+    token_hash   VARCHAR(64)  NOT NULL UNIQUE,
+    expires_at   TIMESTAMPTZ  NOT NULL,
+    confirmed_at TIMESTAMPTZ,
+    created_at   TIMESTAMPTZ  NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_change_requests_user_id ON email_change_requests (user_id);
+
+COMMIT;
@@ -0,0 +1,34 @@
+-- Account deletion with grace period
+--
+-- Deletion is scheduled first and can be cancelled until the grace period
+-- ends. A background job then anonymizes the user row in place so foreign
+-- keys from login_logs, sessions, system_settings and audit_logs stay valid.
+
+BEGIN;
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS deletion_scheduled_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS idx_users_deletion_scheduled_at
+    ON users (deletion_scheduled_at)
+    WHERE deletion_scheduled_at IS NOT NULL;
+
+INSERT INTO system_settings (key, "group", name, value, default_value, type, editable, sensitive, description, sort, created_at, updated_at)
+VALUES (
+    'account.deletion_grace_days',
+    'account',
+    'Deletion grace period',
+    '30'::jsonb,
+    '30'::jsonb,
+    'number',
+    TRUE,
+    FALSE,
+    'Days between a deletion request and erasure of the account',
+    0,
+    now(),
+    now()
+)
+ON CONFLICT (key) DO NOTHING;
+
+COMMIT;
@@ -0,0 +1,23 @@
+-- Personal data exports
+--
+-- Exports are built by a background job into an archive kept in upload
+-- storage until expires_at, after which the file is removed.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS data_exports (
+    id           SERIAL      PRIMARY KEY,
+    user_id      INTEGER     NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    status       VARCHAR(20) NOT NULL DEFAULT 'pending',
+    storage_key  VARCHAR(255),
+    error        TEXT,
+    started_at   TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    expires_at   TIMESTAMPTZ,
+    created_at   TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_data_exports_user_id ON data_exports (user_id);
+CREATE INDEX IF NOT EXISTS idx_data_exports_status ON data_exports (status);
+
+COMMIT;
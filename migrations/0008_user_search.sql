@@ -0,0 +1,14 @@
+-- Fuzzy user search
+--
+-- Trigram GIN indexes back the word-similarity operator (<%) used by
+-- GET /api/users/search. Built CONCURRENTLY so large tables stay writable,
+-- which also means this file must not run inside a transaction.
+
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_users_username_trgm
+    ON users USING gin (username gin_trgm_ops);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_users_email_trgm
+    ON users USING gin (email gin_trgm_ops);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_users_nickname_trgm
+    ON users USING gin (nickname gin_trgm_ops);
@@ -0,0 +1,16 @@
+-- Typed user lifecycle status
+--
+-- users.status becomes a closed set mapped to UserStatus:
+-- 0 suspended (formerly "disabled"), 1 active, 2 pending_verification,
+-- 3 banned, 4 deleted. Ban and erasure state move into the status column;
+-- banned_at and deleted_at remain as timestamps of those transitions.
+
+BEGIN;
+
+UPDATE users SET status = 3 WHERE banned_at IS NOT NULL AND deleted_at IS NULL;
+UPDATE users SET status = 4 WHERE deleted_at IS NOT NULL;
+
+ALTER TABLE users
+    ADD CONSTRAINT users_status_check CHECK (status BETWEEN 0 AND 4);
+
+COMMIT;
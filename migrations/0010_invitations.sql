@@ -0,0 +1,30 @@
+-- User invitations
+--
+-- Admins invite an email address with a preselected role; the invitee picks
+-- their own username and password when accepting. Only the SHA-256 hash of
+-- the emailed token is stored, and resending replaces it.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS invitations (
+    id               SERIAL       PRIMARY KEY,
+    email            VARCHAR(255) NOT NULL,
+    role_id          INTEGER      NOT NULL REFERENCES roles (id) ON DELETE CASCADE,
+    token_hash       VARCHAR(64)  NOT NULL UNIQUE,
+    invited_by       INTEGER      REFERENCES users (id) ON DELETE SET NULL,
+    accepted_user_id INTEGER      REFERENCES users (id) ON DELETE SET NULL,
+    expires_at       TIMESTAMPTZ  NOT NULL,
+    last_sent_at     TIMESTAMPTZ  NOT NULL DEFAULT now(),
+    accepted_at      TIMESTAMPTZ,
+    revoked_at       TIMESTAMPTZ,
+    created_at       TIMESTAMPTZ  NOT NULL DEFAULT now()
+);
+
+-- At most one open invitation per address
+CREATE UNIQUE INDEX IF NOT EXISTS idx_invitations_pending_email
+    ON invitations (lower(email))
+    WHERE accepted_at IS NULL AND revoked_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_invitations_role_id ON invitations (role_id);
+
+COMMIT;
@@ -0,0 +1,14 @@
+-- Per-user preferences
+--
+-- One JSONB document per user. The schema and defaults live in the
+-- application, so missing keys fall back to defaults when read.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS user_preferences (
+    user_id     INTEGER     PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    preferences JSONB       NOT NULL DEFAULT '{}'::jsonb,
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+COMMIT;
@@ -0,0 +1,66 @@
+-- Username changes with history and reservation
+--
+-- Every rename records the previous username. It stays reserved for its
+-- former owner until reserved_until, so nobody else can claim it and
+-- lookups by the old name still resolve to the account.
+
+BEGIN;
+
+CREATE TABLE IF NOT EXISTS username_history (
+    id             SERIAL      PRIMARY KEY,
+    user_id        INTEGER     NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    username       VARCHAR(30) NOT NULL,
+    changed_at     TIMESTAMPTZ NOT NULL DEFAULT now(),
+    reserved_until TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_username_history_username ON username_history (username);
+CREATE INDEX IF NOT EXISTS idx_username_history_user_id ON username_history (user_id, changed_at);
+
+INSERT INTO system_settings (key, "group", name, value, default_value, type, editable, sensitive, description, sort, created_at, updated_at)
+VALUES
+    (
+        'account.username_change_limit',
+        'account',
+        'Username change limit',
+        '2'::jsonb,
+        '2'::jsonb,
+        'number',
+        TRUE,
+        FALSE,
+        'How many times a user may change their username within the change period',
+        1,
+        now(),
+        now()
+    ),
+    (
+        'account.username_change_period_days',
+        'account',
+        'Username change period',
+        '30'::jsonb,
+        '30'::jsonb,
+        'number',
+        TRUE,
+        FALSE,
+        'Rolling window in days over which the username change limit applies',
+        2,
+        now(),
+        now()
+    ),
+    (
+        'account.username_reservation_days',
+        'account',
+        'Username reservation',
+        '90'::jsonb,
+        '90'::jsonb,
+        'number',
+        TRUE,
+        FALSE,
+        'Days a previous username stays reserved for its former owner',
+        3,
+        now(),
+        now()
+    )
+ON CONFLICT (key) DO NOTHING;
+
+COMMIT;
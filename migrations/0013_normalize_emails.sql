@@ -0,0 +1,36 @@
+-- Case-insensitive email uniqueness
+--
+-- Emails are now normalized on write (trimmed, lowercased, IDNA domain).
+-- Existing rows are trimmed and lowercased here; the unique index on
+-- lower(email) then rejects case variants.
+--
+-- Run `cargo run --bin email-collisions` first: it lists accounts whose
+-- addresses collide once normalized, including IDNA-only differences this
+-- migration cannot detect. The migration aborts while collisions remain.
+
+BEGIN;
+
+DO $$
+DECLARE
+    collisions TEXT;
+BEGIN
+    SELECT string_agg(format('%s (user ids %s)', normalized, ids), E'\n')
+    INTO collisions
+    FROM (
+        SELECT lower(btrim(email)) AS normalized, string_agg(id::text, ', ' ORDER BY id) AS ids
+        FROM users
+        GROUP BY lower(btrim(email))
+        HAVING count(*) > 1
+    ) AS duplicates;
+
+    IF collisions IS NOT NULL THEN
+        RAISE EXCEPTION E'Emails collide after normalization; resolve before migrating:\n%', collisions;
+    END IF;
+END
+$$;
+
+UPDATE users SET email = lower(btrim(email)) WHERE email <> lower(btrim(email));
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email_lower ON users (lower(email));
+
+COMMIT;
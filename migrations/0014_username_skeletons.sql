@@ -0,0 +1,49 @@
+-- Unicode usernames with confusable detection
+--
+-- Each username gets a UTS #39 confusable skeleton; the unique index keeps
+-- lookalike names (mixed case, Cyrillic "а" for Latin "a", ...) apart.
+-- Skeletons can only be computed in the application, so existing rows are
+-- filled by `cargo run --bin username-skeletons`, which also reports
+-- accounts whose names already collide.
+
+BEGIN;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS username_skeleton VARCHAR(255);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_username_skeleton
+    ON users (username_skeleton)
+    WHERE username_skeleton IS NOT NULL;
+
+INSERT INTO system_settings (key, "group", name, value, default_value, type, editable, sensitive, description, sort, created_at, updated_at)
+VALUES
+    (
+        'auth.unicode_usernames',
+        'auth',
+        'Unicode usernames',
+        'false'::jsonb,
+        'false'::jsonb,
+        'boolean',
+        TRUE,
+        FALSE,
+        'Allow letters from any single script in usernames instead of ASCII only',
+        1,
+        now(),
+        now()
+    ),
+    (
+        'auth.reserved_usernames',
+        'auth',
+        'Reserved usernames',
+        '["abuse", "admin", "administrator", "api", "help", "hostmaster", "info", "mail", "me", "moderator", "noreply", "null", "postmaster", "root", "security", "staff", "support", "system", "undefined", "webmaster", "www"]'::jsonb,
+        '["abuse", "admin", "administrator", "api", "help", "hostmaster", "info", "mail", "me", "moderator", "noreply", "null", "postmaster", "root", "security", "staff", "support", "system", "undefined", "webmaster", "www"]'::jsonb,
+        'json',
+        TRUE,
+        FALSE,
+        'Usernames nobody may register or rename to, including lookalikes',
+        2,
+        now(),
+        now()
+    )
+ON CONFLICT (key) DO NOTHING;
+
+COMMIT;
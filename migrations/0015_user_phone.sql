@@ -0,0 +1,32 @@
+-- Verified phone numbers with SMS one-time codes
+--
+-- A number is only stored on the user once a code sent to it has been
+-- confirmed. Codes are stored as SHA-256 hashes, expire quickly and lock
+-- after a few wrong guesses. A verified number doubles as a login
+-- identifier and, when enabled, as a second factor at login.
+
+BEGIN;
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS phone             VARCHAR(16),
+    ADD COLUMN IF NOT EXISTS phone_verified_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS sms_mfa_enabled   BOOLEAN NOT NULL DEFAULT false;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_phone ON users (phone);
+
+CREATE TABLE IF NOT EXISTS phone_verifications (
+    id         SERIAL      PRIMARY KEY,
+    user_id    INTEGER     NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    phone      VARCHAR(16) NOT NULL,
+    -- 'verify' confirms a new number, 'login' answers an MFA challenge
+    purpose    VARCHAR(16) NOT NULL,
+    code_hash  VARCHAR(64) NOT NULL,
+    attempts   INTEGER     NOT NULL DEFAULT 0,
+    expires_at TIMESTAMPTZ NOT NULL,
+    used_at    TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_phone_verifications_user_id ON phone_verifications (user_id);
+
+COMMIT;
@@ -0,0 +1,10 @@
+-- Login history
+--
+-- Users and admins page through a user's login attempts newest first, and
+-- every login compares the client against earlier successful ones.
+
+BEGIN;
+
+CREATE INDEX IF NOT EXISTS idx_login_logs_user_login_at ON login_logs (user_id, login_at DESC);
+
+COMMIT;
@@ -0,0 +1,12 @@
+-- Token versions
+--
+-- Access tokens carry the account's token version at issue. Bumping the
+-- version on status changes, password resets and erasure makes the auth
+-- middleware refuse every token issued before.
+
+BEGIN;
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS token_version INTEGER NOT NULL DEFAULT 0;
+
+COMMIT;
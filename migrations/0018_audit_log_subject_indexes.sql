@@ -0,0 +1,15 @@
+-- Audit log subject indexes
+--
+-- Erasing an account scrubs the audit rows about the user and the rows of
+-- the user's own requests. Index both lookups so erasure does not scan the
+-- whole trail.
+
+BEGIN;
+
+CREATE INDEX IF NOT EXISTS idx_audit_logs_entity
+    ON audit_logs (entity, entity_id);
+
+CREATE INDEX IF NOT EXISTS idx_audit_logs_operator_id
+    ON audit_logs (operator_id);
+
+COMMIT;
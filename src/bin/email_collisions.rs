@@ -0,0 +1,82 @@
+use dotenvy::dotenv;
+use saas_axum::{
+    common::{db, validator::normalize_email},
+    entity::users,
+};
+use sea_orm::{EntityTrait, QueryOrder};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Accounts whose addresses become equal once normalized
+#[derive(Serialize)]
+struct Collision {
+    normalized_email: String,
+    users: Vec<CollidingUser>,
+}
+
+#[derive(Serialize)]
+struct CollidingUser {
+    id: i32,
+    username: String,
+    email: String,
+}
+
+/// Report users whose emails collide after normalization
+///
+/// Run before the case-insensitive email migration. Also lists addresses that
+/// no longer pass validation once normalized. Exits non-zero when anything
+/// needs resolving.
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let db_conn = db::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let all_users = users::Entity::find()
+        .order_by_asc(users::Column::Id)
+        .all(&db_conn)
+        .await
+        .expect("Failed to load users");
+
+    let mut groups: BTreeMap<String, Vec<CollidingUser>> = BTreeMap::new();
+    let mut invalid = Vec::new();
+
+    for user in all_users {
+        let entry = CollidingUser {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+        };
+        match normalize_email(&entry.email) {
+            Ok(normalized) => groups.entry(normalized).or_default().push(entry),
+            Err(_) => invalid.push(entry),
+        }
+    }
+
+    let collisions: Vec<Collision> = groups
+        .into_iter()
+        .filter(|(_, users)| users.len() > 1)
+        .map(|(normalized_email, users)| Collision {
+            normalized_email,
+            users,
+        })
+        .collect();
+
+    let clean = collisions.is_empty() && invalid.is_empty();
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&serde_json::json!({
+            "collisions": collisions,
+            "invalid": invalid,
+        }))
+        .expect("Failed to serialize report")
+    );
+
+    if !clean {
+        std::process::exit(1);
+    }
+}
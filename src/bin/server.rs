@@ -1,11 +1,23 @@
 use dotenvy::dotenv;
 use saas_axum::{
-    common::{AppState, db},
+    common::{
+        AppState, db, permission_cache, request_context::TrustedProxies, sms::FileSmsProvider,
+        storage::LocalStorage,
+    },
     create_router,
+    modules::{
+        audit::sink::spawn_audit_writer,
+        role::service as role_service,
+        user::{deletion, export},
+    },
+    route_permissions,
 };
-use std::net::SocketAddr;
+use std::{net::SocketAddr, time::Duration};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+/// Longest the server waits at shutdown for queued audit rows to be written
+const AUDIT_FLUSH_TIMEOUT: Duration = Duration::from_secs(10);
+
 #[tokio::main]
 async fn main() {
     // Load environment variables from .env file
@@ -38,15 +50,71 @@ async fn main() {
         .unwrap_or_else(|_| "604800".to_string())
         .parse()
         .expect("REFRESH_TOKEN_EXPIRATION must be a number");
+    let permission_cache_ttl = std::env::var("PERMISSION_CACHE_TTL")
+        .unwrap_or_else(|_| "300".to_string())
+        .parse()
+        .expect("PERMISSION_CACHE_TTL must be a number");
+
+    // Load upload storage configuration
+    let upload_dir = std::env::var("UPLOAD_DIR").unwrap_or_else(|_| "uploads".to_string());
+    let upload_url = std::env::var("UPLOAD_URL").unwrap_or_else(|_| "/uploads".to_string());
 
     // Create application state
-    let state = AppState::new(
+    let mut state = AppState::new(
         db_conn,
         jwt_secret,
         jwt_expiration,
         refresh_token_expiration,
+        permission_cache_ttl,
+    )
+    .with_storage(LocalStorage::new(upload_dir, upload_url));
+
+    // Write text messages to a local outbox file instead of the log when configured
+    if let Ok(outbox) = std::env::var("SMS_OUTBOX") {
+        state = state.with_sms(FileSmsProvider::new(outbox));
+    }
+
+    // Take client addresses from forwarding headers only when a listed proxy sends them
+    if let Ok(proxies) = std::env::var("TRUSTED_PROXIES") {
+        state = state.with_trusted_proxies(
+            TrustedProxies::parse(&proxies)
+                .expect("TRUSTED_PROXIES must list IP addresses or CIDR ranges"),
+        );
+    }
+
+    // Write request audit rows in the background
+    let (audit_sink, audit_writer) = spawn_audit_writer(state.db.clone());
+    state = state.with_audit_sink(audit_sink);
+
+    // Keep role permission cache coherent across server instances
+    permission_cache::spawn_invalidation_listener(state.db.clone(), state.permission_cache.clone());
+
+    // Erase accounts whose deletion grace period has ended
+    deletion::spawn_erasure_job(
+        state.db.clone(),
+        state.permission_cache.clone(),
+        state.storage.clone(),
     );
 
+    // Build queued data exports and remove expired archives
+    export::spawn_export_job(state.db.clone(), state.storage.clone());
+
+    // Align permissions table with route declarations when requested
+    let sync_permissions = std::env::var("PERMISSION_SYNC")
+        .map(|v| v == "true")
+        .unwrap_or(false);
+    if sync_permissions {
+        let super_admin_role = std::env::var("SUPER_ADMIN_ROLE").ok();
+        role_service::sync_permissions(
+            &state.db,
+            &state.permission_cache,
+            &route_permissions(),
+            super_admin_role.as_deref(),
+        )
+        .await
+        .expect("Failed to sync permissions");
+    }
+
     // Build router with all routes
     let app = create_router(state);
 
@@ -63,5 +131,45 @@ async fn main() {
 
     // Start HTTP server
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .unwrap();
+
+    // The router and its audit sinks are gone, so the writer drains its queue and stops
+    if tokio::time::timeout(AUDIT_FLUSH_TIMEOUT, audit_writer.finish())
+        .await
+        .is_err()
+    {
+        tracing::warn!("Timed out writing queued audit entries");
+    }
+}
+
+/// Resolve on Ctrl+C or SIGTERM, letting in-flight requests finish
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    tracing::info!("Shutting down");
 }
@@ -0,0 +1,50 @@
+use dotenvy::dotenv;
+use saas_axum::{
+    common::{db, permission_cache::PermissionCache},
+    modules::role::service as role_service,
+    route_permissions,
+};
+use std::time::Duration;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+/// Upsert route permissions declared in code into the `permissions` table
+///
+/// Usage: `sync-permissions [super_admin_role]`; falls back to `SUPER_ADMIN_ROLE`.
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "saas_axum=info".into()),
+        )
+        .with(tracing_subscriber::fmt::layer())
+        .init();
+
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let db_conn = db::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let super_admin_role = std::env::args()
+        .nth(1)
+        .or_else(|| std::env::var("SUPER_ADMIN_ROLE").ok());
+
+    // Local cache is unused here; invalidations still reach running servers via NOTIFY
+    let cache = PermissionCache::new(Duration::ZERO);
+
+    let report = role_service::sync_permissions(
+        &db_conn,
+        &cache,
+        &route_permissions(),
+        super_admin_role.as_deref(),
+    )
+    .await
+    .expect("Failed to sync permissions");
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&report).expect("Failed to serialize report")
+    );
+}
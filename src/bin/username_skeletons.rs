@@ -0,0 +1,98 @@
+use dotenvy::dotenv;
+use saas_axum::{
+    common::{db, validator::username_skeleton},
+    entity::users,
+};
+use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait, QueryOrder};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Accounts whose usernames look alike
+#[derive(Serialize)]
+struct Collision {
+    skeleton: String,
+    users: Vec<CollidingUser>,
+}
+
+#[derive(Serialize)]
+struct CollidingUser {
+    id: i32,
+    username: String,
+}
+
+/// Fill `users.username_skeleton` and report lookalike usernames
+///
+/// Users whose skeleton collides with another account are left without one
+/// and listed so they can be renamed; run again afterwards. Exits non-zero
+/// while collisions remain.
+#[tokio::main]
+async fn main() {
+    dotenv().ok();
+
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    let db_conn = db::connect(&database_url)
+        .await
+        .expect("Failed to connect to database");
+
+    let all_users = users::Entity::find()
+        .order_by_asc(users::Column::Id)
+        .all(&db_conn)
+        .await
+        .expect("Failed to load users");
+
+    let mut groups: BTreeMap<String, Vec<users::Model>> = BTreeMap::new();
+    for user in all_users {
+        groups
+            .entry(username_skeleton(&user.username))
+            .or_default()
+            .push(user);
+    }
+
+    let mut updated = 0;
+    let mut collisions = Vec::new();
+
+    for (skeleton, users) in groups {
+        if users.len() > 1 {
+            collisions.push(Collision {
+                skeleton,
+                users: users
+                    .into_iter()
+                    .map(|user| CollidingUser {
+                        id: user.id,
+                        username: user.username,
+                    })
+                    .collect(),
+            });
+            continue;
+        }
+
+        for user in users {
+            if user.username_skeleton.as_deref() == Some(skeleton.as_str()) {
+                continue;
+            }
+
+            let mut active: users::ActiveModel = user.into();
+            active.username_skeleton = Set(Some(skeleton.clone()));
+            active
+                .update(&db_conn)
+                .await
+                .expect("Failed to update username skeleton");
+            updated += 1;
+        }
+    }
+
+    let clean = collisions.is_empty();
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&serde_json::json!({
+            "updated": updated,
+            "collisions": collisions,
+        }))
+        .expect("Failed to serialize report")
+    );
+
+    if !clean {
+        std::process::exit(1);
+    }
+}
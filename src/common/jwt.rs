@@ -2,7 +2,10 @@ use chrono::{Duration, Utc};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, decode, encode};
 use serde::{Deserialize, Serialize};
 
-use crate::common::errors::{AppError, Result};
+use crate::common::{
+    errors::{AppError, Result},
+    permission_cache::PermissionSet,
+};
 
 /// JWT token claims structure with comprehensive user information
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -13,8 +16,8 @@ pub struct Claims {
     /// Username for display
     pub username: String,
 
-    /// User's role ID for authorization
-    pub role_id: i32,
+    /// IDs of every role assigned to the user
+    pub role_ids: Vec<i32>,
 
     /// Token expiration timestamp (Unix timestamp)
     pub exp: i64,
@@ -27,6 +30,18 @@ pub struct Claims {
 
     /// Token type (access or refresh)
     pub token_type: TokenType,
+
+    /// Password must be changed before other requests are accepted
+    #[serde(default)]
+    pub password_change_required: bool,
+
+    /// Account token version at issue; the token is refused once the account moves past it
+    #[serde(default)]
+    pub token_version: i32,
+
+    /// Effective permission slugs, resolved per request by the auth middleware (never encoded in the token)
+    #[serde(skip)]
+    pub permissions: PermissionSet,
 }
 
 /// Token type enumeration
@@ -35,6 +50,68 @@ pub struct Claims {
 pub enum TokenType {
     Access,
     Refresh,
+    Download,
+    Mfa,
+}
+
+/// Claims of a short-lived link granting download of a single file
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DownloadClaims {
+    /// ID of the downloadable resource
+    pub sub: i32,
+
+    /// Owner of the resource
+    pub user_id: i32,
+
+    /// Token expiration timestamp (Unix timestamp)
+    pub exp: i64,
+
+    /// Token issuer
+    pub iss: String,
+
+    /// Always `download`
+    pub token_type: TokenType,
+}
+
+impl DownloadClaims {
+    /// Create claims for resource `id` owned by `user_id`, valid until `exp`
+    pub fn new(id: i32, user_id: i32, exp: i64) -> Self {
+        Self {
+            sub: id,
+            user_id,
+            exp,
+            iss: "saas-axum".to_string(),
+            token_type: TokenType::Download,
+        }
+    }
+}
+
+/// Claims of a short-lived token proving the password step of an MFA login
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MfaClaims {
+    /// User who passed the password check
+    pub sub: i32,
+
+    /// Token expiration timestamp (Unix timestamp)
+    pub exp: i64,
+
+    /// Token issuer
+    pub iss: String,
+
+    /// Always `mfa`
+    pub token_type: TokenType,
+}
+
+impl MfaClaims {
+    /// Create claims for `user_id`, valid until `exp`
+    pub fn new(user_id: i32, exp: i64) -> Self {
+        Self {
+            sub: user_id,
+            exp,
+            iss: "saas-axum".to_string(),
+            token_type: TokenType::Mfa,
+        }
+    }
 }
 
 impl Claims {
@@ -42,7 +119,7 @@ impl Claims {
     pub fn new_access_token(
         user_id: i32,
         username: String,
-        role_id: i32,
+        role_ids: Vec<i32>,
         expiration_seconds: i64,
     ) -> Self {
         let now = Utc::now();
@@ -51,11 +128,14 @@ impl Claims {
         Self {
             sub: user_id,
             username,
-            role_id,
+            role_ids,
             exp,
             iat: now.timestamp(),
             iss: "saas-axum".to_string(),
             token_type: TokenType::Access,
+            password_change_required: false,
+            token_version: 0,
+            permissions: PermissionSet::default(),
         }
     }
 
@@ -63,7 +143,7 @@ impl Claims {
     pub fn new_refresh_token(
         user_id: i32,
         username: String,
-        role_id: i32,
+        role_ids: Vec<i32>,
         expiration_seconds: i64,
     ) -> Self {
         let now = Utc::now();
@@ -72,14 +152,22 @@ impl Claims {
         Self {
             sub: user_id,
             username,
-            role_id,
+            role_ids,
             exp,
             iat: now.timestamp(),
             iss: "saas-axum".to_string(),
             token_type: TokenType::Refresh,
+            password_change_required: false,
+            token_version: 0,
+            permissions: PermissionSet::default(),
         }
     }
 
+    /// Check if the caller has been granted a permission slug
+    pub fn has_permission(&self, slug: &str) -> bool {
+        self.permissions.contains(slug)
+    }
+
     /// Check if token is expired
     pub fn is_expired(&self) -> bool {
         Utc::now().timestamp() > self.exp
@@ -144,3 +232,57 @@ pub fn verify_refresh_token(token: &str, secret: &str) -> Result<Claims> {
 
     Ok(claims)
 }
+
+/// Generate signed download link token
+pub fn generate_download_token(claims: &DownloadClaims, secret: &str) -> Result<String> {
+    encode(
+        &Header::default(),
+        claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .map_err(AppError::from)
+}
+
+/// Verify and decode download link token
+pub fn verify_download_token(token: &str, secret: &str) -> Result<DownloadClaims> {
+    let claims = decode::<DownloadClaims>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map_err(AppError::from)?
+    .claims;
+
+    if claims.token_type != TokenType::Download {
+        return Err(AppError::Unauthorized("Invalid token type".to_string()));
+    }
+
+    Ok(claims)
+}
+
+/// Generate signed MFA challenge token
+pub fn generate_mfa_token(claims: &MfaClaims, secret: &str) -> Result<String> {
+    encode(
+        &Header::default(),
+        claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .map_err(AppError::from)
+}
+
+/// Verify and decode MFA challenge token
+pub fn verify_mfa_token(token: &str, secret: &str) -> Result<MfaClaims> {
+    let claims = decode::<MfaClaims>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map_err(AppError::from)?
+    .claims;
+
+    if claims.token_type != TokenType::Mfa {
+        return Err(AppError::Unauthorized("Invalid token type".to_string()));
+    }
+
+    Ok(claims)
+}
@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+
+use crate::common::errors::Result;
+
+/// Outgoing plain-text email
+#[derive(Debug, Clone)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Email {
+    /// Create email addressed to `to`
+    pub fn new(to: impl Into<String>, subject: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            to: to.into(),
+            subject: subject.into(),
+            body: body.into(),
+        }
+    }
+}
+
+/// Delivery backend for transactional email
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    /// Deliver a single email
+    async fn send(&self, email: Email) -> Result<()>;
+}
+
+/// Mailer that writes messages to the log instead of delivering them
+///
+/// Default backend for development; swap in a real one with `AppState::with_mailer`.
+#[derive(Debug, Clone, Default)]
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: Email) -> Result<()> {
+        tracing::info!(
+            to = %email.to,
+            subject = %email.subject,
+            "📧 Email not delivered (log mailer):\n{}",
+            email.body
+        );
+
+        Ok(())
+    }
+}
@@ -3,14 +3,22 @@
 pub mod db;
 pub mod errors;
 pub mod jwt;
+pub mod mailer;
 pub mod pagination;
 pub mod password;
+pub mod permission_cache;
+pub mod policy;
+pub mod request_context;
 pub mod response;
+pub mod sms;
 pub mod state;
-mod validator;
+pub mod storage;
+pub mod token;
+pub mod validator;
 
 // Re-export commonly used types
 pub use errors::{AppError, Result};
-pub use pagination::PaginationParams;
+pub use pagination::{PaginationParams, SortOrder};
+pub use request_context::RequestContext;
 pub use response::{ApiResponse, PaginatedResponse, success, success_with_message};
 pub use state::AppState;
@@ -1,15 +1,40 @@
-use serde::Deserialize;
+use sea_orm::Order;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 /// Pagination query parameters
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PaginationParams {
+    /// Page number starting at 1
     #[serde(default = "default_page")]
+    #[param(example = 1)]
     pub page: u64,
 
+    /// Items per page (1-100)
     #[serde(default = "default_page_size")]
+    #[param(example = 20)]
     pub page_size: u64,
 }
 
+/// Sort direction query parameter
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl From<SortOrder> for Order {
+    fn from(order: SortOrder) -> Self {
+        match order {
+            SortOrder::Asc => Order::Asc,
+            SortOrder::Desc => Order::Desc,
+        }
+    }
+}
+
 fn default_page() -> u64 {
     1
 }
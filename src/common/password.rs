@@ -1,6 +1,9 @@
 use argon2::{
     Argon2,
-    password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString, rand_core::OsRng},
+    password_hash::{
+        PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+        rand_core::{OsRng, RngCore},
+    },
 };
 
 use crate::common::errors::{AppError, Result};
@@ -32,6 +35,43 @@ pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
         .is_ok())
 }
 
+/// Character classes used for generated passwords (ambiguous characters removed)
+const PASSWORD_CHARSETS: [&[u8]; 4] = [
+    b"ABCDEFGHJKLMNPQRSTUVWXYZ",
+    b"abcdefghijkmnopqrstuvwxyz",
+    b"23456789",
+    b"!@#$%^&*-_=+?",
+];
+
+/// Length of generated temporary passwords
+const TEMPORARY_PASSWORD_LENGTH: usize = 16;
+
+/// Generate random temporary password that satisfies [`validate_password_strength`]
+pub fn generate_temporary_password() -> String {
+    let mut rng = OsRng;
+    let pick = |rng: &mut OsRng, charset: &[u8]| {
+        charset[(rng.next_u32() as usize) % charset.len()] as char
+    };
+
+    // One character from each class, then fill from the combined set
+    let mut chars: Vec<char> = PASSWORD_CHARSETS
+        .iter()
+        .map(|charset| pick(&mut rng, charset))
+        .collect();
+    let all: Vec<u8> = PASSWORD_CHARSETS.concat();
+    while chars.len() < TEMPORARY_PASSWORD_LENGTH {
+        chars.push(pick(&mut rng, &all));
+    }
+
+    // Fisher-Yates shuffle so the guaranteed classes are not always first
+    for i in (1..chars.len()).rev() {
+        let j = (rng.next_u32() as usize) % (i + 1);
+        chars.swap(i, j);
+    }
+
+    chars.into_iter().collect()
+}
+
 /// Validate password strength
 pub fn validate_password_strength(password: &str) -> Result<()> {
     if password.len() < 8 {
@@ -76,3 +116,27 @@ pub fn validate_password_strength(password: &str) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temporary_passwords_pass_strength_rules() {
+        for _ in 0..200 {
+            let password = generate_temporary_password();
+            assert_eq!(password.len(), TEMPORARY_PASSWORD_LENGTH);
+            assert!(
+                validate_password_strength(&password).is_ok(),
+                "weak temporary password {}",
+                password
+            );
+        }
+    }
+
+    #[test]
+    fn temporary_passwords_avoid_ambiguous_characters() {
+        let password = generate_temporary_password();
+        assert!(!password.contains(['0', 'O', '1', 'l', 'I']));
+    }
+}
@@ -0,0 +1,316 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use sea_orm::{
+    ConnectionTrait, DatabaseConnection, DbBackend, Statement, sqlx::postgres::PgListener,
+};
+
+use crate::{common::errors::Result, entity::sea_orm_active_enums::UserStatus};
+
+/// Postgres NOTIFY channel used to broadcast invalidations between server instances
+pub const INVALIDATION_CHANNEL: &str = "role_permission_cache";
+
+/// Notification payload meaning "drop every cached role and account"
+const INVALIDATE_ALL_PAYLOAD: &str = "*";
+
+/// Prefix of notification payloads naming a single account
+const USER_PAYLOAD_PREFIX: &str = "user:";
+
+/// Delay before reconnecting a dropped invalidation listener
+const LISTENER_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Resolved set of permission slugs for a role
+pub type PermissionSet = Arc<HashSet<String>>;
+
+/// Cached resolution of a single role
+#[derive(Debug, Clone, Default)]
+pub struct CachedRole {
+    /// Whether the role is enabled; disabled roles grant nothing
+    pub active: bool,
+
+    /// Effective permission slugs of the role
+    pub permissions: PermissionSet,
+}
+
+/// Cached account state checked on every authenticated request
+#[derive(Debug, Clone)]
+pub struct CachedUser {
+    /// Token version access tokens must carry to be accepted
+    pub token_version: i32,
+
+    /// Lifecycle status
+    pub status: UserStatus,
+
+    /// Whether the account has been erased
+    pub deleted: bool,
+
+    /// Assigned role IDs
+    pub role_ids: Vec<i32>,
+
+    /// Current username
+    pub username: String,
+
+    /// Whether the user must change their password before anything else
+    pub password_change_required: bool,
+}
+
+/// Scope of a cache invalidation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Invalidation {
+    /// A single role's permissions changed
+    Role(i32),
+
+    /// A single account's status, token version, roles or username changed
+    User(i32),
+
+    /// Changes that may affect any role or account (bulk updates, reconnects)
+    All,
+}
+
+impl Invalidation {
+    fn to_payload(self) -> String {
+        match self {
+            Invalidation::Role(role_id) => role_id.to_string(),
+            Invalidation::User(user_id) => format!("{}{}", USER_PAYLOAD_PREFIX, user_id),
+            Invalidation::All => INVALIDATE_ALL_PAYLOAD.to_string(),
+        }
+    }
+
+    fn from_payload(payload: &str) -> Self {
+        // Unknown payloads fall back to a full flush to stay on the safe side
+        match payload.strip_prefix(USER_PAYLOAD_PREFIX) {
+            Some(user_id) => user_id.parse().map(Invalidation::User),
+            None => payload.parse().map(Invalidation::Role),
+        }
+        .unwrap_or(Invalidation::All)
+    }
+}
+
+struct CacheEntry<T> {
+    value: T,
+    loaded_at: Instant,
+}
+
+type Entries<T> = Arc<RwLock<HashMap<i32, CacheEntry<T>>>>;
+
+/// In-process cache with TTL expiry of role_id -> role status and permission
+/// slugs, and of user_id -> the account state tokens are checked against
+#[derive(Clone)]
+pub struct PermissionCache {
+    entries: Entries<CachedRole>,
+    users: Entries<CachedUser>,
+    /// Bumped on every invalidation so loads that raced one are not stored
+    generation: Arc<AtomicU64>,
+    ttl: Duration,
+}
+
+impl PermissionCache {
+    /// Create empty cache whose entries expire after `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Arc::default(),
+            users: Arc::default(),
+            generation: Arc::new(AtomicU64::new(0)),
+            ttl,
+        }
+    }
+
+    /// Get cached resolution of a role, ignoring expired entries
+    pub fn get(&self, role_id: i32) -> Option<CachedRole> {
+        self.lookup(&self.entries, role_id)
+    }
+
+    /// Get cached state of an account, ignoring expired entries
+    pub fn get_user(&self, user_id: i32) -> Option<CachedUser> {
+        self.lookup(&self.users, user_id)
+    }
+
+    /// Current invalidation generation; read it before loading an entry to insert
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Store a role resolution loaded at `generation`
+    ///
+    /// The role is returned but not cached when an invalidation happened
+    /// since, as the load may have read data from before the change.
+    pub fn insert(&self, role_id: i32, role: CachedRole, generation: u64) -> CachedRole {
+        self.store(&self.entries, role_id, role, generation)
+    }
+
+    /// Store account state loaded at `generation`, like [`PermissionCache::insert`]
+    pub fn insert_user(&self, user_id: i32, user: CachedUser, generation: u64) -> CachedUser {
+        self.store(&self.users, user_id, user, generation)
+    }
+
+    /// Drop cached entries in this process only
+    pub fn invalidate_local(&self, scope: Invalidation) {
+        // Hold both locks so no insert sees the old generation after the removal
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        let mut users = self.users.write().unwrap_or_else(|e| e.into_inner());
+        self.generation.fetch_add(1, Ordering::AcqRel);
+
+        match scope {
+            Invalidation::Role(role_id) => {
+                entries.remove(&role_id);
+            }
+            Invalidation::User(user_id) => {
+                users.remove(&user_id);
+            }
+            Invalidation::All => {
+                entries.clear();
+                users.clear();
+            }
+        }
+    }
+
+    /// Drop cached entries here and notify every other instance
+    pub async fn invalidate(&self, db: &DatabaseConnection, scope: Invalidation) -> Result<()> {
+        self.invalidate_local(scope);
+
+        db.execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT pg_notify($1, $2)",
+            [INVALIDATION_CHANNEL.into(), scope.to_payload().into()],
+        ))
+        .await?;
+
+        Ok(())
+    }
+
+    fn lookup<T: Clone>(&self, entries: &Entries<T>, id: i32) -> Option<T> {
+        let entries = entries.read().unwrap_or_else(|e| e.into_inner());
+
+        entries
+            .get(&id)
+            .filter(|entry| entry.loaded_at.elapsed() < self.ttl)
+            .map(|entry| entry.value.clone())
+    }
+
+    fn store<T: Clone>(&self, entries: &Entries<T>, id: i32, value: T, generation: u64) -> T {
+        let mut entries = entries.write().unwrap_or_else(|e| e.into_inner());
+        if self.generation() != generation {
+            return value;
+        }
+
+        entries.insert(
+            id,
+            CacheEntry {
+                value: value.clone(),
+                loaded_at: Instant::now(),
+            },
+        );
+
+        value
+    }
+}
+
+/// Spawn background task applying invalidations published by other instances
+pub fn spawn_invalidation_listener(db: DatabaseConnection, cache: PermissionCache) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = listen_for_invalidations(&db, &cache).await {
+                tracing::warn!("Permission cache listener error: {}", e);
+            }
+
+            // Stop serving entries nobody is invalidating any more
+            cache.invalidate_local(Invalidation::All);
+            tokio::time::sleep(LISTENER_RETRY_DELAY).await;
+        }
+    });
+}
+
+async fn listen_for_invalidations(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+) -> std::result::Result<(), sea_orm::sqlx::Error> {
+    let mut listener = PgListener::connect_with(db.get_postgres_connection_pool()).await?;
+    listener.listen(INVALIDATION_CHANNEL).await?;
+
+    // Notifications may have been missed while disconnected, including
+    // during the retry delay
+    cache.invalidate_local(Invalidation::All);
+
+    tracing::info!("Permission cache listening on '{}'", INVALIDATION_CHANNEL);
+
+    // `try_recv` yields None when the connection drops, so the caller can flush and retry
+    while let Some(notification) = listener.try_recv().await? {
+        cache.invalidate_local(Invalidation::from_payload(notification.payload()));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn role(slug: &str) -> CachedRole {
+        CachedRole {
+            active: true,
+            permissions: Arc::new(HashSet::from([slug.to_string()])),
+        }
+    }
+
+    #[test]
+    fn payloads_round_trip() {
+        for scope in [
+            Invalidation::Role(42),
+            Invalidation::User(42),
+            Invalidation::All,
+        ] {
+            assert_eq!(Invalidation::from_payload(&scope.to_payload()), scope);
+        }
+    }
+
+    #[test]
+    fn unknown_payloads_flush_everything() {
+        assert_eq!(Invalidation::from_payload(""), Invalidation::All);
+        assert_eq!(Invalidation::from_payload("role:7"), Invalidation::All);
+        assert_eq!(Invalidation::from_payload("user:me"), Invalidation::All);
+    }
+
+    #[test]
+    fn skips_loads_that_raced_an_invalidation() {
+        let cache = PermissionCache::new(Duration::from_secs(60));
+
+        let generation = cache.generation();
+        cache.invalidate_local(Invalidation::Role(1));
+        cache.insert(1, role("users:read"), generation);
+        assert!(cache.get(1).is_none());
+
+        cache.insert(1, role("users:read"), cache.generation());
+        assert!(cache.get(1).is_some());
+
+        cache.invalidate_local(Invalidation::All);
+        assert!(cache.get(1).is_none());
+    }
+
+    #[test]
+    fn user_invalidation_leaves_roles_cached() {
+        let cache = PermissionCache::new(Duration::from_secs(60));
+        let user = CachedUser {
+            token_version: 3,
+            status: UserStatus::Active,
+            deleted: false,
+            role_ids: vec![1],
+            username: "alice".to_string(),
+            password_change_required: false,
+        };
+        cache.insert(1, role("users:read"), cache.generation());
+        cache.insert_user(7, user, cache.generation());
+
+        cache.invalidate_local(Invalidation::User(7));
+        assert!(cache.get_user(7).is_none());
+        assert!(cache.get(1).is_some());
+
+        cache.invalidate_local(Invalidation::Role(1));
+        assert!(cache.get(1).is_none());
+    }
+}
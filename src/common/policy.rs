@@ -0,0 +1,81 @@
+use std::fmt;
+
+use crate::common::{
+    errors::{AppError, Result},
+    jwt::Claims,
+};
+
+/// Operation a caller wants to perform on a resource
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Read,
+    Create,
+    Update,
+    Delete,
+}
+
+impl Action {
+    /// Action name as used in permission slugs
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Read => "read",
+            Action::Create => "create",
+            Action::Update => "update",
+            Action::Delete => "delete",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Resource guarded by [`authorize`]
+///
+/// Modules implement this for their entities to declare the permission
+/// namespace, who owns an instance and any attribute-based restrictions.
+pub trait Resource {
+    /// Permission namespace; role permissions are matched as `{KIND}:{action}`
+    const KIND: &'static str;
+
+    /// Actions the owner may perform without holding the role permission
+    const OWNER_ACTIONS: &'static [Action] = &[];
+
+    /// ID of the user owning this resource, if any
+    fn owner_id(&self) -> Option<i32> {
+        None
+    }
+
+    /// Attribute rules applied once access has been granted; return an error to deny
+    fn check_attributes(&self, _claims: &Claims, _action: Action) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Build permission slug checked for an action on a resource kind
+pub fn permission_slug(kind: &str, action: Action) -> String {
+    format!("{}:{}", kind, action)
+}
+
+/// Authorize an action on a resource for the authenticated caller
+///
+/// Access is granted by the `{KIND}:{action}` role permission or, for the
+/// resource's `OWNER_ACTIONS`, by owning it. Attribute rules run last and may
+/// still deny an otherwise permitted action.
+pub fn authorize<R: Resource>(claims: &Claims, action: Action, resource: &R) -> Result<()> {
+    let granted_by_role = claims.has_permission(&permission_slug(R::KIND, action));
+    let granted_by_ownership =
+        R::OWNER_ACTIONS.contains(&action) && resource.owner_id() == Some(claims.sub);
+
+    if !granted_by_role && !granted_by_ownership {
+        return Err(AppError::Forbidden(format!(
+            "Not allowed to {} this {}",
+            action,
+            R::KIND
+        )));
+    }
+
+    resource.check_attributes(claims, action)
+}
@@ -0,0 +1,245 @@
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, OriginalUri},
+    http::{HeaderMap, request::Parts},
+};
+use std::{
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+/// Request metadata recorded in audit and login logs
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    /// HTTP method, e.g. `POST`
+    pub method: String,
+
+    /// Full request path including the `/api` prefix
+    pub path: String,
+
+    /// Client IP; forwarding headers count only when sent by a trusted proxy
+    pub ip_address: Option<String>,
+
+    /// Client user agent
+    pub user_agent: Option<String>,
+
+    /// Correlation ID supplied by the client or a proxy
+    pub request_id: Option<String>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for RequestContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // Nested routers strip their prefix from `uri`, so prefer the original
+        let path = parts
+            .extensions
+            .get::<OriginalUri>()
+            .map(|uri| uri.path().to_string())
+            .unwrap_or_else(|| parts.uri.path().to_string());
+
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let trusted = parts
+            .extensions
+            .get::<TrustedProxies>()
+            .cloned()
+            .unwrap_or_default();
+
+        Ok(Self {
+            method: parts.method.to_string(),
+            path,
+            ip_address: peer.map(|peer| client_ip(peer, &parts.headers, &trusted).to_string()),
+            user_agent: header_value(&parts.headers, "user-agent"),
+            request_id: header_value(&parts.headers, "x-request-id"),
+        })
+    }
+}
+
+/// Proxies whose forwarding headers are believed, as addresses or CIDR ranges
+///
+/// Read from request extensions; without it every forwarding header is
+/// ignored and the socket address is the client.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Arc<[ProxyRange]>);
+
+#[derive(Debug, Clone, Copy)]
+struct ProxyRange {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl TrustedProxies {
+    /// Parse a comma-separated list such as `10.0.0.0/8, 192.168.1.10`
+    pub fn parse(list: &str) -> std::result::Result<Self, String> {
+        let ranges = list
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (address, prefix) = entry.split_once('/').unwrap_or((entry, ""));
+                let network: IpAddr = address
+                    .parse()
+                    .map_err(|_| format!("Invalid proxy address '{}'", entry))?;
+                let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+                let prefix = match prefix {
+                    "" => max_prefix,
+                    prefix => prefix
+                        .parse()
+                        .ok()
+                        .filter(|prefix| *prefix <= max_prefix)
+                        .ok_or_else(|| format!("Invalid proxy prefix '{}'", entry))?,
+                };
+                Ok(ProxyRange {
+                    network: network.to_canonical(),
+                    prefix,
+                })
+            })
+            .collect::<std::result::Result<Vec<_>, String>>()?;
+
+        Ok(Self(ranges.into()))
+    }
+
+    /// Whether `ip` belongs to one of the trusted proxies
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.0.iter().any(|range| match (range.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - range.prefix as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - range.prefix as u32)
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        })
+    }
+}
+
+/// Client address of a request that reached us from `peer`
+///
+/// Forwarding headers are only believed from trusted proxies. The client is
+/// the right-most `X-Forwarded-For` hop that is not a trusted proxy, since
+/// everything left of it could have been sent by the client itself;
+/// `X-Real-IP` is used when a trusted proxy sends no `X-Forwarded-For`.
+fn client_ip(peer: IpAddr, headers: &HeaderMap, trusted: &TrustedProxies) -> IpAddr {
+    if !trusted.contains(peer) {
+        return peer;
+    }
+
+    let hops: Vec<&str> = headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    if hops.is_empty() {
+        return header_value(headers, "x-real-ip")
+            .and_then(|ip| ip.trim().parse().ok())
+            .unwrap_or(peer);
+    }
+
+    let mut client = peer;
+    for hop in hops.into_iter().rev() {
+        // An unparseable hop ends the chain at the last proxy that vouched for it
+        let Ok(ip) = hop.parse::<IpAddr>() else {
+            break;
+        };
+        client = ip;
+        if !trusted.contains(ip) {
+            break;
+        }
+    }
+    client
+}
+
+fn header_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn ignores_forwarding_headers_from_untrusted_peers() {
+        let trusted = TrustedProxies::parse("10.0.0.0/8").unwrap();
+        let headers = headers(&[("x-forwarded-for", "1.2.3.4"), ("x-real-ip", "1.2.3.4")]);
+
+        assert_eq!(
+            client_ip(ip("203.0.113.7"), &headers, &trusted),
+            ip("203.0.113.7")
+        );
+        assert_eq!(
+            client_ip(ip("10.0.0.1"), &headers, &TrustedProxies::default()),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn takes_right_most_untrusted_hop() {
+        let trusted = TrustedProxies::parse("10.0.0.0/8, 192.168.1.10").unwrap();
+        let headers = headers(&[
+            ("x-forwarded-for", "6.6.6.6, 203.0.113.7"),
+            ("x-forwarded-for", "192.168.1.10"),
+        ]);
+
+        assert_eq!(
+            client_ip(ip("10.1.2.3"), &headers, &trusted),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn stops_at_unparseable_hops() {
+        let trusted = TrustedProxies::parse("10.0.0.1").unwrap();
+        let headers = headers(&[("x-forwarded-for", "203.0.113.7, junk, 10.0.0.1")]);
+
+        assert_eq!(
+            client_ip(ip("10.0.0.1"), &headers, &trusted),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_real_ip_header() {
+        let trusted = TrustedProxies::parse("::1, 127.0.0.1").unwrap();
+        let headers = headers(&[("x-real-ip", "203.0.113.7")]);
+
+        assert_eq!(
+            client_ip(ip("::ffff:127.0.0.1"), &headers, &trusted),
+            ip("203.0.113.7")
+        );
+        assert_eq!(client_ip(ip("::1"), &HeaderMap::new(), &trusted), ip("::1"));
+    }
+
+    #[test]
+    fn rejects_invalid_proxy_entries() {
+        assert!(TrustedProxies::parse("10.0.0.0/33").is_err());
+        assert!(TrustedProxies::parse("proxy.internal").is_err());
+        assert!(TrustedProxies::parse("").unwrap().0.is_empty());
+    }
+}
@@ -72,7 +72,7 @@ pub fn success_with_message<T: Serialize>(data: T, message: impl Into<String>) -
 impl<T: Serialize> PaginatedResponse<T> {
     /// Create paginated response
     pub fn new(data: Vec<T>, page: u64, page_size: u64, total_items: u64) -> Self {
-        let total_pages = (total_items + page_size - 1) / page_size;
+        let total_pages = total_items.div_ceil(page_size);
 
         Self {
             code: 200,
@@ -0,0 +1,84 @@
+use async_trait::async_trait;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+
+use crate::common::errors::{AppError, Result};
+
+/// Outgoing text message
+#[derive(Debug, Clone)]
+pub struct Sms {
+    pub to: String,
+    pub body: String,
+}
+
+impl Sms {
+    /// Create message addressed to phone number `to`
+    pub fn new(to: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            to: to.into(),
+            body: body.into(),
+        }
+    }
+}
+
+/// Delivery backend for text messages such as one-time codes
+#[async_trait]
+pub trait SmsProvider: Send + Sync {
+    /// Deliver a single message
+    async fn send(&self, sms: Sms) -> Result<()>;
+}
+
+/// Provider that writes messages to the log instead of delivering them
+///
+/// Default backend for development; swap in a real one with `AppState::with_sms`.
+#[derive(Debug, Clone, Default)]
+pub struct LogSmsProvider;
+
+#[async_trait]
+impl SmsProvider for LogSmsProvider {
+    async fn send(&self, sms: Sms) -> Result<()> {
+        tracing::info!(to = %sms.to, "📱 SMS not delivered (log provider): {}", sms.body);
+
+        Ok(())
+    }
+}
+
+/// Provider that appends each message as a JSON line to a file
+///
+/// Lets tests and local tooling read one-time codes without a real gateway.
+#[derive(Debug, Clone)]
+pub struct FileSmsProvider {
+    path: PathBuf,
+}
+
+impl FileSmsProvider {
+    /// Append messages to the file at `path`, creating it when missing
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl SmsProvider for FileSmsProvider {
+    async fn send(&self, sms: Sms) -> Result<()> {
+        let mut line = serde_json::json!({
+            "to": sms.to,
+            "body": sms.body,
+            "sent_at": chrono::Utc::now(),
+        })
+        .to_string();
+        line.push('\n');
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to open SMS outbox: {}", e)))?;
+        file.write_all(line.as_bytes())
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to write SMS outbox: {}", e)))?;
+
+        Ok(())
+    }
+}
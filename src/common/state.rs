@@ -1,4 +1,16 @@
 use sea_orm::DatabaseConnection;
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    common::{
+        mailer::{LogMailer, Mailer},
+        permission_cache::PermissionCache,
+        request_context::TrustedProxies,
+        sms::{LogSmsProvider, SmsProvider},
+        storage::{LocalStorage, Storage},
+    },
+    modules::audit::sink::AuditSink,
+};
 
 /// Global application state shared across all handlers
 #[derive(Clone)]
@@ -14,6 +26,24 @@ pub struct AppState {
 
     /// Refresh token expiration in seconds
     pub refresh_token_expiration: i64,
+
+    /// Cache of resolved role permissions
+    pub permission_cache: PermissionCache,
+
+    /// Transactional email backend
+    pub mailer: Arc<dyn Mailer>,
+
+    /// Backend for uploaded files such as avatars
+    pub storage: Arc<dyn Storage>,
+
+    /// Text message backend for one-time codes
+    pub sms: Arc<dyn SmsProvider>,
+
+    /// Background writer for request audit rows; requests are not audited without one
+    pub audit_sink: Option<AuditSink>,
+
+    /// Proxies allowed to report the client address in forwarding headers
+    pub trusted_proxies: TrustedProxies,
 }
 
 impl AppState {
@@ -23,12 +53,49 @@ impl AppState {
         jwt_secret: String,
         jwt_expiration: i64,
         refresh_token_expiration: i64,
+        permission_cache_ttl: u64,
     ) -> Self {
         Self {
             db,
             jwt_secret,
             jwt_expiration,
             refresh_token_expiration,
+            permission_cache: PermissionCache::new(Duration::from_secs(permission_cache_ttl)),
+            mailer: Arc::new(LogMailer),
+            storage: Arc::new(LocalStorage::new("uploads", "/uploads")),
+            sms: Arc::new(LogSmsProvider),
+            audit_sink: None,
+            trusted_proxies: TrustedProxies::default(),
         }
     }
+
+    /// Replace the default log mailer
+    pub fn with_mailer(mut self, mailer: impl Mailer + 'static) -> Self {
+        self.mailer = Arc::new(mailer);
+        self
+    }
+
+    /// Replace the default local upload storage
+    pub fn with_storage(mut self, storage: impl Storage + 'static) -> Self {
+        self.storage = Arc::new(storage);
+        self
+    }
+
+    /// Replace the default log SMS provider
+    pub fn with_sms(mut self, sms: impl SmsProvider + 'static) -> Self {
+        self.sms = Arc::new(sms);
+        self
+    }
+
+    /// Audit every mutating request through this sink
+    pub fn with_audit_sink(mut self, sink: AuditSink) -> Self {
+        self.audit_sink = Some(sink);
+        self
+    }
+
+    /// Believe forwarding headers sent by these proxies
+    pub fn with_trusted_proxies(mut self, proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = proxies;
+        self
+    }
 }
@@ -0,0 +1,105 @@
+use async_trait::async_trait;
+use std::path::{Component, Path, PathBuf};
+
+use crate::common::errors::{AppError, Result};
+
+/// Backend for user uploaded files addressed by slash separated keys
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Store `bytes` under `key`, replacing any existing object
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()>;
+
+    /// Read the object under `key`
+    async fn get(&self, key: &str) -> Result<Vec<u8>>;
+
+    /// Remove the object under `key`; missing objects are not an error
+    async fn delete(&self, key: &str) -> Result<()>;
+
+    /// Public URL the object under `key` is served from
+    fn url(&self, key: &str) -> String;
+
+    /// Directory to serve files from when the backend is the local filesystem
+    fn local_root(&self) -> Option<&Path> {
+        None
+    }
+}
+
+/// Storage writing files below a local directory
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    root: PathBuf,
+    base_url: String,
+}
+
+impl LocalStorage {
+    /// Create storage rooted at `root` whose files are served under `base_url`
+    pub fn new(root: impl Into<PathBuf>, base_url: impl Into<String>) -> Self {
+        Self {
+            root: root.into(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Resolve key to a path, refusing anything that could escape the root
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        let relative = Path::new(key);
+        let is_safe = relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+
+        if key.is_empty() || !is_safe {
+            return Err(AppError::Internal(format!("Invalid storage key '{}'", key)));
+        }
+
+        Ok(self.root.join(relative))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        let path = self.path(key)?;
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| AppError::Internal(format!("Failed to create directory: {}", e)))?;
+        }
+
+        // Write to a sibling temp file first so readers never see a partial file
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, bytes)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to write file: {}", e)))?;
+        tokio::fs::rename(&tmp, &path)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to write file: {}", e)))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>> {
+        tokio::fs::read(self.path(key)?)
+            .await
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => AppError::NotFound("File not found".to_string()),
+                _ => AppError::Internal(format!("Failed to read file: {}", e)),
+            })
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(AppError::Internal(format!("Failed to delete file: {}", e))),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.base_url, key)
+    }
+
+    fn local_root(&self) -> Option<&Path> {
+        Some(&self.root)
+    }
+}
@@ -0,0 +1,28 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// Number of random bytes in a generated token
+const TOKEN_BYTES: usize = 32;
+
+/// Number of digits in a one-time code sent by SMS
+const OTP_DIGITS: usize = 6;
+
+/// Generate an opaque URL-safe token for one-time links
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Generate a numeric one-time code short enough to type from a text message
+pub fn generate_otp() -> String {
+    let bound = 10u32.pow(OTP_DIGITS as u32);
+
+    format!("{:0width$}", OsRng.next_u32() % bound, width = OTP_DIGITS)
+}
+
+/// Hash token for storage so a database leak does not expose usable tokens
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
@@ -1,7 +1,8 @@
 use crate::common::errors::{AppError, Result};
 use once_cell::sync::Lazy;
 use regex::Regex;
-use validator::ValidationError;
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::{RestrictionLevel, RestrictionLevelDetection, skeleton};
 
 /// Email validation regex pattern
 static EMAIL_REGEX: Lazy<Regex> =
@@ -13,6 +14,10 @@ static USERNAME_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^[a-zA-Z0-9_]{3,3
 /// Phone number validation regex pattern (international format)
 static PHONE_NUMBER_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\+?[1-9]\d{1,14}$").unwrap());
 
+/// Locale validation regex pattern (BCP 47 language, optional script and region)
+static LOCALE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^[a-z]{2,3}(-[A-Z][a-z]{3})?(-([A-Z]{2}|[0-9]{3}))?$").unwrap());
+
 /// Validate email format
 pub fn validate_email(email: &str) -> Result<()> {
     if !EMAIL_REGEX.is_match(email) {
@@ -24,6 +29,27 @@ pub fn validate_email(email: &str) -> Result<()> {
     Ok(())
 }
 
+/// Normalize an email for storage and lookup
+///
+/// Trims whitespace, lowercases the local part and converts the domain to its
+/// lowercase IDNA (punycode) form, so `Bob@Bücher.example` and
+/// `bob@xn--bcher-kva.example` compare equal.
+pub fn normalize_email(email: &str) -> Result<String> {
+    let email = email.trim();
+    let invalid = || AppError::ValidationError("Invalid email format".to_string());
+
+    let (local, domain) = email.rsplit_once('@').ok_or_else(invalid)?;
+    if local.is_empty() || domain.is_empty() {
+        return Err(invalid());
+    }
+
+    let domain = idna::domain_to_ascii(domain).map_err(|_| invalid())?;
+    let normalized = format!("{}@{}", local.to_lowercase(), domain);
+    validate_email(&normalized)?;
+
+    Ok(normalized)
+}
+
 /// Validate username format
 pub fn validate_username(username: &str) -> Result<()> {
     if !USERNAME_REGEX.is_match(username) {
@@ -35,6 +61,47 @@ pub fn validate_username(username: &str) -> Result<()> {
     Ok(())
 }
 
+/// Normalize a username with NFKC and validate it
+///
+/// ASCII-only unless `allow_unicode` is set. Unicode names must be letters,
+/// digits and underscores from a single script (UTS #39 highly restrictive),
+/// which rules out mixed-script lookalikes such as Latin with Cyrillic.
+pub fn normalize_username(username: &str, allow_unicode: bool) -> Result<String> {
+    let normalized: String = username.trim().nfkc().collect();
+
+    if !allow_unicode {
+        validate_username(&normalized)?;
+        return Ok(normalized);
+    }
+
+    let length = normalized.chars().count();
+    if !(3..=30).contains(&length) || !normalized.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(AppError::ValidationError(
+            "Username must be 3-30 characters long and contain only letters, numbers, and underscores".to_string(),
+        ));
+    }
+
+    if !normalized
+        .as_str()
+        .check_restriction_level(RestrictionLevel::HighlyRestrictive)
+    {
+        return Err(AppError::ValidationError(
+            "Username must not mix characters from different scripts".to_string(),
+        ));
+    }
+
+    Ok(normalized)
+}
+
+/// UTS #39 confusable skeleton of a username, case-folded
+///
+/// Two usernames with the same skeleton look alike, e.g. `paypal` and `pаypal`
+/// with a Cyrillic `а`, or `admin` and `AdMIn`.
+pub fn username_skeleton(username: &str) -> String {
+    let folded: String = username.nfkc().collect::<String>().to_lowercase();
+    skeleton(&folded).collect::<String>().to_lowercase()
+}
+
 /// Validate phone number format
 pub fn validate_phone(phone: &str) -> Result<()> {
     if !PHONE_NUMBER_REGEX.is_match(phone) {
@@ -46,6 +113,47 @@ pub fn validate_phone(phone: &str) -> Result<()> {
     Ok(())
 }
 
+/// Normalize a phone number to E.164 for storage and lookup
+///
+/// Strips spaces and common punctuation, so `+1 (415) 555-0123` becomes
+/// `+14155550123`. The country code is required.
+pub fn normalize_phone(phone: &str) -> Result<String> {
+    let normalized: String = phone
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+        .collect();
+
+    if !normalized.starts_with('+') {
+        return Err(AppError::ValidationError(
+            "Phone number must include the country code, e.g. +14155550123".to_string(),
+        ));
+    }
+    validate_phone(&normalized)?;
+
+    Ok(normalized)
+}
+
+/// Validate locale format
+pub fn validate_locale(locale: &str) -> Result<()> {
+    if !LOCALE_REGEX.is_match(locale) {
+        return Err(AppError::ValidationError(
+            "Locale must be a BCP 47 language tag such as en-US".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Validate IANA time zone name
+pub fn validate_timezone(timezone: &str) -> Result<()> {
+    if timezone.parse::<chrono_tz::Tz>().is_err() {
+        return Err(AppError::ValidationError("Unknown time zone".to_string()));
+    }
+
+    Ok(())
+}
+
 /// Sanitize iser input to prevent XSS attacks
 pub fn sanitize_input(input: &str) -> String {
     input
@@ -55,3 +163,83 @@ pub fn sanitize_input(input: &str) -> String {
         .replace('\'', "&#x27;")
         .replace('&', "&amp;")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_emails_for_lookup() {
+        assert_eq!(
+            normalize_email("  Bob.Smith@Example.COM ").unwrap(),
+            "bob.smith@example.com"
+        );
+        assert_eq!(
+            normalize_email("Bob@Bücher.example").unwrap(),
+            normalize_email("bob@xn--bcher-kva.example").unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_emails() {
+        for email in [
+            "bob",
+            "@example.com",
+            "bob@",
+            "bob@example",
+            "b ob@example.com",
+        ] {
+            assert!(normalize_email(email).is_err(), "accepted {}", email);
+        }
+    }
+
+    #[test]
+    fn lookalike_usernames_share_a_skeleton() {
+        assert_eq!(username_skeleton("AdMIn"), username_skeleton("admin"));
+        assert_eq!(username_skeleton("pаypal"), username_skeleton("paypal"));
+        assert_eq!(username_skeleton("adrnin"), username_skeleton("admin"));
+        assert_ne!(username_skeleton("alice"), username_skeleton("alicia"));
+    }
+
+    #[test]
+    fn normalizes_usernames_with_nfkc() {
+        assert_eq!(
+            normalize_username(" ｊｏｈｎ_doe ", false).unwrap(),
+            "john_doe"
+        );
+        assert_eq!(normalize_username("Jürgen", true).unwrap(), "Jürgen");
+        assert!(normalize_username("Jürgen", false).is_err());
+    }
+
+    #[test]
+    fn rejects_mixed_script_usernames() {
+        assert!(normalize_username("pаypal", true).is_err());
+        assert!(normalize_username("Иван", true).is_ok());
+        assert!(normalize_username("ab", true).is_err());
+        assert!(normalize_username("john doe", true).is_err());
+    }
+
+    #[test]
+    fn normalizes_phone_numbers_to_e164() {
+        assert_eq!(
+            normalize_phone(" +1 (415) 555-0123 ").unwrap(),
+            "+14155550123"
+        );
+        assert_eq!(
+            normalize_phone("+44.20.7946.0958").unwrap(),
+            "+442079460958"
+        );
+    }
+
+    #[test]
+    fn rejects_phone_numbers_without_country_code() {
+        for phone in [
+            "4155550123",
+            "+0123456",
+            "+1 415 CALL NOW",
+            "+1234567890123456",
+        ] {
+            assert!(normalize_phone(phone).is_err(), "accepted {}", phone);
+        }
+    }
+}
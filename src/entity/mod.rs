@@ -3,12 +3,19 @@
 pub mod prelude;
 
 pub mod audit_logs;
+pub mod data_exports;
+pub mod email_change_requests;
 pub mod intentions;
+pub mod invitations;
 pub mod login_logs;
 pub mod permissions;
+pub mod phone_verifications;
 pub mod role_permissions;
 pub mod roles;
 pub mod sea_orm_active_enums;
 pub mod sessions;
 pub mod system_settings;
+pub mod user_preferences;
+pub mod user_roles;
+pub mod username_history;
 pub mod users;
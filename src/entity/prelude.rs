@@ -1,11 +1,18 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
 pub use super::audit_logs::Entity as AuditLogs;
+pub use super::data_exports::Entity as DataExports;
+pub use super::email_change_requests::Entity as EmailChangeRequests;
 pub use super::intentions::Entity as Intentions;
+pub use super::invitations::Entity as Invitations;
 pub use super::login_logs::Entity as LoginLogs;
 pub use super::permissions::Entity as Permissions;
+pub use super::phone_verifications::Entity as PhoneVerifications;
 pub use super::role_permissions::Entity as RolePermissions;
 pub use super::roles::Entity as Roles;
 pub use super::sessions::Entity as Sessions;
 pub use super::system_settings::Entity as SystemSettings;
+pub use super::user_preferences::Entity as UserPreferences;
+pub use super::user_roles::Entity as UserRoles;
+pub use super::username_history::Entity as UsernameHistory;
 pub use super::users::Entity as Users;
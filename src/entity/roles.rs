@@ -15,14 +15,31 @@ pub struct Model {
     pub status: i32,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
+    pub parent_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::invitations::Entity")]
+    Invitations,
     #[sea_orm(has_many = "super::role_permissions::Entity")]
     RolePermissions,
-    #[sea_orm(has_many = "super::users::Entity")]
-    Users,
+    #[sea_orm(
+        belongs_to = "Entity",
+        from = "Column::ParentId",
+        to = "Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    SelfRef,
+    #[sea_orm(has_many = "super::user_roles::Entity")]
+    UserRoles,
+}
+
+impl Related<super::invitations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Invitations.def()
+    }
 }
 
 impl Related<super::role_permissions::Entity> for Entity {
@@ -31,9 +48,18 @@ impl Related<super::role_permissions::Entity> for Entity {
     }
 }
 
+impl Related<super::user_roles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserRoles.def()
+    }
+}
+
 impl Related<super::users::Entity> for Entity {
     fn to() -> RelationDef {
-        Relation::Users.def()
+        super::user_roles::Relation::Users.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::user_roles::Relation::Roles.def().rev())
     }
 }
 
@@ -17,3 +17,31 @@ pub enum IntentionStatus {
     #[sea_orm(string_value = "spam")]
     Spam,
 }
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    EnumIter,
+    DeriveActiveEnum,
+    Serialize,
+    Deserialize,
+    utoipa::ToSchema,
+)]
+#[sea_orm(rs_type = "i32", db_type = "Integer")]
+#[serde(rename_all = "snake_case")]
+pub enum UserStatus {
+    #[sea_orm(num_value = 0)]
+    Suspended,
+    #[sea_orm(num_value = 1)]
+    Active,
+    #[sea_orm(num_value = 2)]
+    PendingVerification,
+    #[sea_orm(num_value = 3)]
+    Banned,
+    #[sea_orm(num_value = 4)]
+    Deleted,
+}
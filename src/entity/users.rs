@@ -1,5 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
+use super::sea_orm_active_enums::UserStatus;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
@@ -20,26 +21,50 @@ pub struct Model {
     #[sea_orm(unique)]
     pub username: String,
     pub avatar: Option<String>,
-    pub role_id: Option<i32>,
-    pub status: i32,
+    pub status: UserStatus,
+    pub password_change_required: bool,
+    pub deletion_scheduled_at: Option<DateTimeWithTimeZone>,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub username_skeleton: Option<String>,
+    #[sea_orm(unique)]
+    pub phone: Option<String>,
+    pub phone_verified_at: Option<DateTimeWithTimeZone>,
+    pub sms_mfa_enabled: bool,
+    pub token_version: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::data_exports::Entity")]
+    DataExports,
+    #[sea_orm(has_many = "super::email_change_requests::Entity")]
+    EmailChangeRequests,
     #[sea_orm(has_many = "super::login_logs::Entity")]
     LoginLogs,
-    #[sea_orm(
-        belongs_to = "super::roles::Entity",
-        from = "Column::RoleId",
-        to = "super::roles::Column::Id",
-        on_update = "NoAction",
-        on_delete = "Restrict"
-    )]
-    Roles,
+    #[sea_orm(has_many = "super::phone_verifications::Entity")]
+    PhoneVerifications,
     #[sea_orm(has_many = "super::sessions::Entity")]
     Sessions,
     #[sea_orm(has_many = "super::system_settings::Entity")]
     SystemSettings,
+    #[sea_orm(has_one = "super::user_preferences::Entity")]
+    UserPreferences,
+    #[sea_orm(has_many = "super::user_roles::Entity")]
+    UserRoles,
+    #[sea_orm(has_many = "super::username_history::Entity")]
+    UsernameHistory,
+}
+
+impl Related<super::data_exports::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DataExports.def()
+    }
+}
+
+impl Related<super::email_change_requests::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::EmailChangeRequests.def()
+    }
 }
 
 impl Related<super::login_logs::Entity> for Entity {
@@ -48,9 +73,18 @@ impl Related<super::login_logs::Entity> for Entity {
     }
 }
 
+impl Related<super::phone_verifications::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PhoneVerifications.def()
+    }
+}
+
 impl Related<super::roles::Entity> for Entity {
     fn to() -> RelationDef {
-        Relation::Roles.def()
+        super::user_roles::Relation::Roles.def()
+    }
+    fn via() -> Option<RelationDef> {
+        Some(super::user_roles::Relation::Users.def().rev())
     }
 }
 
@@ -66,4 +100,22 @@ impl Related<super::system_settings::Entity> for Entity {
     }
 }
 
+impl Related<super::user_preferences::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserPreferences.def()
+    }
+}
+
+impl Related<super::user_roles::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UserRoles.def()
+    }
+}
+
+impl Related<super::username_history::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::UsernameHistory.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -5,18 +5,25 @@ pub mod middleware;
 pub mod modules;
 
 use axum::{
-    Router,
+    Extension, Router,
+    extract::DefaultBodyLimit,
     middleware::from_fn_with_state,
-    routing::{get, post},
+    routing::{get, post, put},
+};
+use tower_http::{
+    cors::{Any, CorsLayer},
+    services::ServeDir,
 };
-use tower_http::cors::{Any, CorsLayer};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::{
     common::AppState,
-    middleware::auth_middleware,
-    modules::{auth, user},
+    middleware::{
+        PermissionRouterExt, RoutePermission, audit_middleware, auth_middleware,
+        password_change_middleware,
+    },
+    modules::{auth, role, user},
 };
 
 /// OpenAPI documentation structure
@@ -24,23 +31,116 @@ use crate::{
 #[openapi(
     paths(
         auth::handlers::login_handler,
+        auth::handlers::verify_mfa_handler,
         auth::handlers::register_handler,
+        auth::handlers::change_password_handler,
         user::handlers::get_current_user,
+        user::handlers::update_current_user,
+        user::handlers::change_username,
+        user::handlers::get_preferences,
+        user::handlers::update_preferences,
+        user::handlers::delete_current_user,
+        user::handlers::cancel_current_user_deletion,
+        user::handlers::upload_avatar,
+        user::handlers::request_data_export,
+        user::handlers::get_data_export,
+        user::handlers::download_data_export,
+        user::handlers::request_email_change,
+        user::handlers::confirm_email_change,
+        user::handlers::add_phone,
+        user::handlers::verify_phone,
+        user::handlers::remove_phone,
+        user::handlers::update_sms_mfa,
+        user::handlers::get_login_history,
+        user::handlers::get_user_login_history,
         user::handlers::list_users,
+        user::handlers::search_users,
+        user::handlers::export_users,
+        user::handlers::get_user,
+        user::handlers::get_user_by_username,
+        user::handlers::create_user,
+        user::handlers::import_users,
+        user::handlers::list_invitations,
+        user::handlers::create_invitation,
+        user::handlers::resend_invitation,
+        user::handlers::revoke_invitation,
+        user::handlers::accept_invitation,
+        user::handlers::update_user,
+        user::handlers::delete_user,
+        user::handlers::cancel_user_deletion,
+        user::handlers::assign_roles,
+        user::handlers::update_user_status,
+        user::handlers::reset_user_password,
+        role::handlers::get_role_permissions,
+        role::handlers::set_role_parent,
+        role::handlers::set_role_status,
     ),
     components(
         schemas(
             auth::dto::LoginRequest,
             auth::dto::RegisterRequest,
             auth::dto::AuthResponse,
+            auth::dto::LoginResponse,
+            auth::dto::MfaChallenge,
+            auth::dto::VerifyMfaRequest,
             auth::dto::UserInfo,
+            auth::dto::ChangePasswordRequest,
             user::dto::UserProfile,
+            user::dto::UserPreferences,
+            user::dto::NotificationPreferences,
+            user::dto::Theme,
+            user::dto::DateFormat,
+            user::dto::UpdatePreferencesRequest,
+            user::dto::ChangeUsernameRequest,
+            user::dto::UpdateNotificationPreferences,
+            entity::sea_orm_active_enums::UserStatus,
             user::dto::UserListItem,
+            user::dto::UserSortField,
+            user::dto::CreateUserRequest,
+            user::dto::UpdateUserRequest,
+            user::dto::AssignRolesRequest,
+            user::dto::UpdateUserStatusRequest,
+            user::dto::PasswordResetResponse,
+            user::dto::UpdateProfileRequest,
+            user::dto::ChangeEmailRequest,
+            user::dto::ConfirmEmailChangeRequest,
+            user::dto::EmailChangePending,
+            user::dto::AddPhoneRequest,
+            user::dto::VerifyPhoneRequest,
+            user::dto::RemovePhoneRequest,
+            user::dto::UpdateSmsMfaRequest,
+            user::dto::PhoneVerificationPending,
+            user::dto::LoginHistoryItem,
+            user::dto::AvatarUpload,
+            user::dto::AvatarThumbnail,
+            user::dto::AvatarResponse,
+            user::dto::DeleteAccountRequest,
+            user::dto::AccountDeletionResponse,
+            user::dto::DataExportResponse,
+            user::dto::CredentialDelivery,
+            user::dto::UserImportUpload,
+            user::dto::ImportRowError,
+            user::dto::ImportedUser,
+            user::dto::ImportedInvitation,
+            user::dto::ImportReport,
+            user::dto::CreateInvitationRequest,
+            user::dto::InvitationResponse,
+            user::dto::AcceptInvitationRequest,
+            user::dto::ExportFormat,
+            user::dto::SearchHighlight,
+            user::dto::UserSearchResult,
+            common::SortOrder,
+            role::dto::RoleSummary,
+            role::dto::SetRoleParentRequest,
+            role::dto::UpdateRoleStatusRequest,
+            role::dto::InheritedPermission,
+            role::dto::RolePermissionsResponse,
         )
     ),
     tags(
         (name = "Authentication", description = "Authentication endpoints for login and registration"),
-        (name = "Users", description = "User management endpoints")
+        (name = "Users", description = "User management endpoints"),
+        (name = "Roles", description = "Role and permission management endpoints")
     ),
     modifiers(&SecurityAddon)
 )]
@@ -74,22 +174,172 @@ pub fn create_router(state: AppState) -> Router {
     // Public routes accessible without authentication
     let public_routes = Router::new()
         .route("/auth/login", post(auth::handlers::login_handler))
+        .route("/auth/login/mfa", post(auth::handlers::verify_mfa_handler))
         .route("/auth/register", post(auth::handlers::register_handler))
+        .route(
+            "/exports/download",
+            get(user::handlers::download_data_export),
+        )
+        .route(
+            "/invitations/accept",
+            post(user::handlers::accept_invitation),
+        )
         .route("/health", get(health_check));
 
     // Protected routes requiring authentication
     let protected_routes = Router::new()
-        .route("/users/me", get(user::handlers::get_current_user))
-        .route("/users", get(user::handlers::list_users))
+        .route(
+            "/users/me",
+            get(user::handlers::get_current_user)
+                .patch(user::handlers::update_current_user)
+                .delete(user::handlers::delete_current_user),
+        )
+        .route("/users/me/username", put(user::handlers::change_username))
+        .route(
+            "/users/me/preferences",
+            get(user::handlers::get_preferences).patch(user::handlers::update_preferences),
+        )
+        .route(
+            "/users/me/exports",
+            post(user::handlers::request_data_export),
+        )
+        .route(
+            "/users/me/exports/:id",
+            get(user::handlers::get_data_export),
+        )
+        .route(
+            "/users/me/deletion/cancel",
+            post(user::handlers::cancel_current_user_deletion),
+        )
+        .route(
+            "/users/me/avatar",
+            // Leave room for multipart framing around the image itself
+            post(user::handlers::upload_avatar).layer(DefaultBodyLimit::max(
+                user::avatar::MAX_AVATAR_BYTES + 64 * 1024,
+            )),
+        )
+        .route(
+            "/users/me/email",
+            post(user::handlers::request_email_change),
+        )
+        .route(
+            "/users/me/email/confirm",
+            post(user::handlers::confirm_email_change),
+        )
+        .route(
+            "/users/me/phone",
+            put(user::handlers::add_phone).delete(user::handlers::remove_phone),
+        )
+        .route("/users/me/phone/verify", post(user::handlers::verify_phone))
+        .route("/users/me/mfa/sms", put(user::handlers::update_sms_mfa))
+        .route(
+            "/users/me/login-history",
+            get(user::handlers::get_login_history),
+        )
+        .permission_route(&user::permissions::LIST, user::handlers::list_users)
+        .permission_route(&user::permissions::SEARCH, user::handlers::search_users)
+        .permission_route(&user::permissions::EXPORT, user::handlers::export_users)
+        .permission_route(&user::permissions::READ, user::handlers::get_user)
+        .permission_route(
+            &user::permissions::LOGIN_HISTORY,
+            user::handlers::get_user_login_history,
+        )
+        .permission_route(
+            &user::permissions::LOOKUP,
+            user::handlers::get_user_by_username,
+        )
+        .permission_route(&user::permissions::CREATE, user::handlers::create_user)
+        .permission_route(&user::permissions::IMPORT, user::handlers::import_users)
+        .permission_route(
+            &user::permissions::LIST_INVITATIONS,
+            user::handlers::list_invitations,
+        )
+        .permission_route(
+            &user::permissions::INVITE,
+            user::handlers::create_invitation,
+        )
+        .permission_route(
+            &user::permissions::RESEND_INVITATION,
+            user::handlers::resend_invitation,
+        )
+        .permission_route(
+            &user::permissions::REVOKE_INVITATION,
+            user::handlers::revoke_invitation,
+        )
+        .permission_route(&user::permissions::UPDATE, user::handlers::update_user)
+        .permission_route(&user::permissions::DELETE, user::handlers::delete_user)
+        .permission_route(
+            &user::permissions::CANCEL_DELETION,
+            user::handlers::cancel_user_deletion,
+        )
+        .permission_route(
+            &user::permissions::ASSIGN_ROLES,
+            user::handlers::assign_roles,
+        )
+        .permission_route(
+            &user::permissions::UPDATE_STATUS,
+            user::handlers::update_user_status,
+        )
+        .permission_route(
+            &user::permissions::RESET_PASSWORD,
+            user::handlers::reset_user_password,
+        )
+        .permission_route(
+            &role::permissions::READ_PERMISSIONS,
+            role::handlers::get_role_permissions,
+        )
+        .permission_route(
+            &role::permissions::SET_PARENT,
+            role::handlers::set_role_parent,
+        )
+        .permission_route(
+            &role::permissions::SET_STATUS,
+            role::handlers::set_role_status,
+        )
         .route_layer(from_fn_with_state(state.clone(), auth_middleware));
 
+    // Password change stays reachable while a forced change is pending
+    let password_change_routes = Router::new()
+        .route(
+            "/auth/change-password",
+            post(auth::handlers::change_password_handler),
+        )
+        .route_layer(from_fn_with_state(
+            state.clone(),
+            password_change_middleware,
+        ));
+
     // Combine all routes under /api prefix
-    Router::new()
+    let mut router = Router::new()
         .merge(SwaggerUi::new("/docs").url("/api-docs/openapi.json", ApiDoc::openapi()))
         .nest("/api", public_routes)
         .nest("/api", protected_routes)
-        .layer(cors)
-        .with_state(state)
+        .nest("/api", password_change_routes);
+
+    // Record mutating requests once they complete, when an audit writer is running
+    if let Some(sink) = state.audit_sink.clone() {
+        router = router.layer(from_fn_with_state(sink, audit_middleware));
+    }
+
+    // Resolve client addresses behind the configured proxies
+    router = router.layer(Extension(state.trusted_proxies.clone()));
+
+    // Serve public uploads directly when they are stored on the local filesystem;
+    // other prefixes such as data exports are only reachable through signed links
+    if let Some(root) = state.storage.local_root() {
+        router = router.nest_service("/uploads/avatars", ServeDir::new(root.join("avatars")));
+    }
+
+    router.layer(cors).with_state(state)
+}
+
+/// Every permission declared by routes in [`create_router`]
+pub fn route_permissions() -> Vec<&'static RoutePermission> {
+    [user::permissions::ALL, role::permissions::ALL]
+        .into_iter()
+        .flatten()
+        .copied()
+        .collect()
 }
 
 /// Health check endpoint
@@ -0,0 +1,268 @@
+use axum::{
+    body::{Body, to_bytes},
+    extract::{MatchedPath, Query, Request, State},
+    http::{Method, header},
+    middleware::Next,
+    response::Response,
+};
+use sea_orm::Set;
+use serde_json::Value;
+use std::{collections::HashMap, time::Instant};
+
+use crate::{
+    common::{RequestContext, jwt::Claims},
+    entity::audit_logs,
+    modules::audit::{change::collect_changes, service::RiskLevel, sink::AuditSink},
+};
+
+/// Action stored on request-level audit rows
+const REQUEST_ACTION: &str = "http.request";
+
+/// Operator ID recorded for requests made without a valid token
+const ANONYMOUS_OPERATOR_ID: i32 = 0;
+
+/// Largest JSON body copied into `request_params`
+const MAX_CAPTURED_BODY_BYTES: usize = 64 * 1024;
+
+/// Request fields masked before parameters are stored: secrets, then personal data
+///
+/// Login identifiers count as personal data since `username` may carry an
+/// email address or phone number.
+const SENSITIVE_PARAMS: &[&str] = &[
+    "password",
+    "old_password",
+    "new_password",
+    "token",
+    "refresh_token",
+    "mfa_token",
+    "code",
+    "email",
+    "new_email",
+    "phone",
+    "username",
+];
+
+/// Middleware recording every mutating request in `audit_logs` once it completes
+///
+/// Rows are handed to the background writer behind [`AuditSink`], so the
+/// database write never delays the response. The operator comes from the
+/// claims that the auth middleware copies into the response. Entity changes
+/// recorded by services during a successful request replace the entity
+/// derived from the path and are listed in `metadata.changes` as references
+/// to their own audit rows, which hold the before/after diff.
+pub async fn audit_middleware(
+    State(sink): State<AuditSink>,
+    ctx: RequestContext,
+    req: Request,
+    next: Next,
+) -> Response {
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(req).await;
+    }
+
+    let started = Instant::now();
+    let created_at = chrono::Utc::now();
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
+    let query_params = Query::<HashMap<String, String>>::try_from_uri(req.uri())
+        .ok()
+        .filter(|Query(params)| !params.is_empty())
+        .map(|Query(params)| redact(serde_json::json!(params)));
+    let (req, request_params) = capture_json_body(req).await;
+
+    let (response, mut changes) = collect_changes(next.run(req)).await;
+
+    let status = response.status();
+    let claims = response.extensions().get::<Claims>();
+
+    // Changes from a failed request were rolled back or never happened
+    if !status.is_success() {
+        changes.clear();
+    }
+    let metadata = (!changes.is_empty()).then(|| serde_json::json!({ "changes": changes }));
+    let (entity, entity_id) = match changes.into_iter().next() {
+        Some(change) => (change.entity, change.entity_id),
+        None => entity_from_path(route.as_deref(), &ctx.path),
+    };
+    let level = if status.is_server_error() {
+        "error"
+    } else if status.is_client_error() {
+        "warn"
+    } else {
+        "info"
+    };
+    let completed_at = chrono::Utc::now();
+
+    sink.push(audit_logs::ActiveModel {
+        request_id: Set(ctx.request_id.clone()),
+        level: Set(level.to_string()),
+        risk_level: Set(RiskLevel::Low.as_str().to_string()),
+        entity: Set(entity),
+        entity_id: Set(entity_id),
+        action: Set(REQUEST_ACTION.to_string()),
+        api_path: Set(ctx.path),
+        http_method: Set(ctx.method),
+        operator_id: Set(claims.map_or(ANONYMOUS_OPERATOR_ID, |claims| claims.sub)),
+        operator_name: Set(claims.map(|claims| claims.username.clone())),
+        metadata: Set(metadata),
+        operator_role: Set(claims.map(|claims| {
+            claims
+                .role_ids
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        })),
+        ip_address: Set(ctx.ip_address),
+        user_agent: Set(ctx.user_agent),
+        request_params: Set(request_params),
+        query_params: Set(query_params),
+        status: Set(if status.is_success() || status.is_redirection() {
+            "success"
+        } else {
+            "failed"
+        }
+        .to_string()),
+        error_message: Set((!status.is_success() && !status.is_redirection())
+            .then(|| status.canonical_reason().unwrap_or_default().to_string())),
+        http_status_code: Set(Some(i32::from(status.as_u16()))),
+        created_at: Set(created_at.into()),
+        completed_at: Set(Some(completed_at.into())),
+        duration: Set(Some(
+            i32::try_from(started.elapsed().as_millis()).unwrap_or(i32::MAX),
+        )),
+        ..Default::default()
+    });
+
+    response
+}
+
+/// Copy a small JSON body for the audit row and hand the request on unchanged
+///
+/// Bodies that are not JSON, have no declared length or exceed
+/// [`MAX_CAPTURED_BODY_BYTES`] are not read, so uploads stream through.
+async fn capture_json_body(req: Request) -> (Request, Option<Value>) {
+    let is_json = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    let fits = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok())
+        .is_some_and(|length| length <= MAX_CAPTURED_BODY_BYTES);
+    if !is_json || !fits {
+        return (req, None);
+    }
+
+    let (parts, body) = req.into_parts();
+    let bytes = match to_bytes(body, MAX_CAPTURED_BODY_BYTES).await {
+        Ok(bytes) => bytes,
+        // Body was cut short; let the handler report the broken request
+        Err(_) => return (Request::from_parts(parts, Body::empty()), None),
+    };
+    let params = serde_json::from_slice(&bytes).ok().map(redact);
+
+    (Request::from_parts(parts, Body::from(bytes)), params)
+}
+
+/// Mask sensitive fields at any depth
+fn redact(mut value: Value) -> Value {
+    match &mut value {
+        Value::Object(fields) => {
+            for (key, field) in fields.iter_mut() {
+                if SENSITIVE_PARAMS.contains(&key.as_str()) {
+                    *field = Value::String("***".to_string());
+                } else {
+                    *field = redact(field.take());
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items.iter_mut() {
+                *item = redact(item.take());
+            }
+        }
+        _ => {}
+    }
+
+    value
+}
+
+/// Resource and ID addressed by a request, e.g. `("users", "42")` for `/api/users/:id`
+///
+/// The ID is the first path parameter of the matched route, or empty for
+/// collection routes.
+fn entity_from_path(route: Option<&str>, path: &str) -> (String, String) {
+    let template = route.unwrap_or(path);
+    let mut segments = template
+        .trim_start_matches('/')
+        .split('/')
+        .skip_while(|segment| *segment == "api");
+    let entity = segments.next().unwrap_or_default().to_string();
+
+    let entity_id = template
+        .split('/')
+        .zip(path.split('/'))
+        .find(|(segment, _)| segment.starts_with(':'))
+        .map(|(_, value)| value.to_string())
+        .unwrap_or_default();
+
+    (entity, entity_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn redacts_sensitive_fields_at_any_depth() {
+        let params = json!({
+            "username": "bob@example.com",
+            "password": "Secret#123",
+            "nickname": "Bob",
+            "rows": [{ "email": "a@example.com", "role_id": 2 }],
+            "profile": { "phone": "+14155550123", "tags": ["x"] },
+        });
+
+        assert_eq!(
+            redact(params),
+            json!({
+                "username": "***",
+                "password": "***",
+                "nickname": "Bob",
+                "rows": [{ "email": "***", "role_id": 2 }],
+                "profile": { "phone": "***", "tags": ["x"] },
+            })
+        );
+    }
+
+    #[test]
+    fn entity_comes_from_the_matched_route() {
+        assert_eq!(
+            entity_from_path(Some("/api/users/:id/roles"), "/api/users/42/roles"),
+            ("users".to_string(), "42".to_string())
+        );
+        assert_eq!(
+            entity_from_path(Some("/api/users/me/exports/:id"), "/api/users/me/exports/7"),
+            ("users".to_string(), "7".to_string())
+        );
+    }
+
+    #[test]
+    fn collection_routes_have_no_entity_id() {
+        assert_eq!(
+            entity_from_path(Some("/api/users"), "/api/users"),
+            ("users".to_string(), String::new())
+        );
+        assert_eq!(
+            entity_from_path(None, "/api/auth/login"),
+            ("auth".to_string(), String::new())
+        );
+    }
+}
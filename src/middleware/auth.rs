@@ -5,14 +5,66 @@ use axum::{
     response::Response,
 };
 
-use crate::common::{AppState, jwt::verify_token};
+use crate::{
+    common::{
+        AppError, AppState,
+        jwt::{Claims, verify_token},
+    },
+    modules::{auth::service as auth_service, role::service as role_service},
+};
 
 /// Middleware to verify JWT token and inject user claims into request
+///
+/// Tokens flagged for a forced password change are refused here; only routes
+/// behind [`password_change_middleware`] accept them.
 pub async fn auth_middleware(
     State(state): State<AppState>,
-    mut req: Request,
+    req: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let req = authenticate(&state, req).await?;
+
+    // Block everything except the password change until the user picks a new one
+    let must_change_password = req
+        .extensions()
+        .get::<Claims>()
+        .is_some_and(|claims| claims.password_change_required);
+    if must_change_password {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    // Continue processing request
+    Ok(run_with_claims(req, next).await)
+}
+
+/// Middleware for the password change route, accepting tokens that must change password
+pub async fn password_change_middleware(
+    State(state): State<AppState>,
+    req: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
+    let req = authenticate(&state, req).await?;
+
+    Ok(run_with_claims(req, next).await)
+}
+
+/// Run the handler and copy the caller's claims into the response
+///
+/// Layers outside the router, such as request auditing, cannot see request
+/// extensions added here, so they read the operator from the response.
+async fn run_with_claims(req: Request, next: Next) -> Response {
+    let claims = req.extensions().get::<Claims>().cloned();
+
+    let mut response = next.run(req).await;
+    if let Some(claims) = claims {
+        response.extensions_mut().insert(claims);
+    }
+
+    response
+}
+
+/// Verify bearer token against the current account, resolve roles and permissions, and attach claims to the request
+async fn authenticate(state: &AppState, mut req: Request) -> Result<Request, StatusCode> {
     // Extract Authorization header from request
     let auth_header = req
         .headers()
@@ -26,11 +78,44 @@ pub async fn auth_middleware(
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
     // Verify token and extract claims
-    let claims = verify_token(token, &state.jwt_secret).map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let mut claims =
+        verify_token(token, &state.jwt_secret).map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+    // Refuse revoked tokens and pick up role changes made since issuance
+    auth_service::refresh_claims(&state.db, &state.permission_cache, &mut claims)
+        .await
+        .map_err(|e| match e {
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            e => {
+                tracing::error!("Failed to load token account: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+
+    // Refuse tokens whose roles were disabled after issuance
+    role_service::ensure_active_roles(&state.db, &state.permission_cache, &claims.role_ids)
+        .await
+        .map_err(|e| match e {
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            e => {
+                tracing::error!("Failed to resolve roles: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        })?;
+
+    // Resolve effective permissions for policy checks downstream
+    let permissions =
+        role_service::user_permissions(&state.db, &state.permission_cache, &claims.role_ids)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to resolve permissions: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+    claims.permissions = permissions.into();
 
     // Inject claims into request extensions for downstream handlers
     req.extensions_mut().insert(claims);
 
-    // Continue processing request
-    Ok(next.run(req).await)
+    Ok(req)
 }
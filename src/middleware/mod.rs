@@ -1,3 +1,7 @@
+pub mod audit;
 pub mod auth;
+pub mod permission;
 
-pub use auth::auth_middleware;
+pub use audit::audit_middleware;
+pub use auth::{auth_middleware, password_change_middleware};
+pub use permission::{PermissionRouterExt, RoutePermission, require_permission};
@@ -0,0 +1,86 @@
+use axum::{
+    Extension, Router,
+    extract::{Request, State},
+    handler::Handler,
+    http::Method,
+    middleware::{Next, from_fn_with_state},
+    response::Response,
+    routing::{MethodFilter, on},
+};
+
+use crate::common::{AppError, AppState, jwt::Claims};
+
+/// Permission required by a route, declared in code next to its module
+#[derive(Debug, Clone)]
+pub struct RoutePermission {
+    /// Permission slug checked against the caller's roles
+    pub slug: &'static str,
+
+    /// Human readable permission name
+    pub name: &'static str,
+
+    /// HTTP method of the guarded route
+    pub method: Method,
+
+    /// Route path relative to the `/api` prefix, in axum syntax
+    pub path: &'static str,
+}
+
+impl RoutePermission {
+    /// API pattern stored in `permissions.api`, e.g. `GET /api/users/{id}`
+    pub fn api(&self) -> String {
+        let path = self
+            .path
+            .split('/')
+            .map(|segment| match segment.strip_prefix(':') {
+                Some(param) => format!("{{{}}}", param),
+                None => segment.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        format!("{} /api{}", self.method, path)
+    }
+}
+
+/// Router extension registering routes under their declared permission
+pub trait PermissionRouterExt {
+    /// Route `permission.method permission.path` to `handler`, guarded by `permission.slug`
+    fn permission_route<H, T>(self, permission: &'static RoutePermission, handler: H) -> Self
+    where
+        H: Handler<T, AppState>,
+        T: 'static;
+}
+
+impl PermissionRouterExt for Router<AppState> {
+    fn permission_route<H, T>(self, permission: &'static RoutePermission, handler: H) -> Self
+    where
+        H: Handler<T, AppState>,
+        T: 'static,
+    {
+        let filter = MethodFilter::try_from(permission.method.clone())
+            .expect("Route permission declares an unsupported HTTP method");
+
+        self.route(
+            permission.path,
+            on(filter, handler).route_layer(from_fn_with_state(permission, require_permission)),
+        )
+    }
+}
+
+/// Middleware rejecting callers whose roles lack the route's permission
+pub async fn require_permission(
+    State(permission): State<&'static RoutePermission>,
+    Extension(claims): Extension<Claims>,
+    req: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if !claims.has_permission(permission.slug) {
+        return Err(AppError::Forbidden(format!(
+            "Missing permission '{}'",
+            permission.slug
+        )));
+    }
+
+    Ok(next.run(req).await)
+}
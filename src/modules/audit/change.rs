@@ -0,0 +1,185 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::{
+    future::Future,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use crate::modules::audit::service::snapshot;
+
+tokio::task_local! {
+    /// Changes attached while handling the current request
+    static REQUEST_CHANGES: Arc<Mutex<Vec<RecordedChange>>>;
+}
+
+/// Snapshots of one entity taken around a write
+///
+/// Start with [`capture`](Self::capture) before updating or deleting a
+/// model and finish with [`updated`](Self::updated) or
+/// [`deleted`](Self::deleted); use [`created`](Self::created) for inserts.
+#[derive(Debug, Clone, Default)]
+pub struct EntityChange {
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl EntityChange {
+    /// Snapshot a model that was just created
+    pub fn created<M: Serialize>(model: &M) -> Self {
+        Self {
+            before: None,
+            after: Some(snapshot(model)),
+        }
+    }
+
+    /// Snapshot a model before updating or deleting it
+    pub fn capture<M: Serialize>(model: &M) -> Self {
+        Self {
+            before: Some(snapshot(model)),
+            after: None,
+        }
+    }
+
+    /// Record the model's state after the update
+    pub fn updated<M: Serialize>(self, model: &M) -> Self {
+        Self {
+            after: Some(snapshot(model)),
+            ..self
+        }
+    }
+
+    /// Record that the model was deleted
+    pub fn deleted(self) -> Self {
+        Self {
+            after: None,
+            ..self
+        }
+    }
+}
+
+/// Reference to the audit row recording a change, destined for the request's audit row
+///
+/// The snapshots and diff live on the referenced row only. Attach it once
+/// the transaction holding that row has committed.
+#[must_use = "attach the change once its transaction commits"]
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedChange {
+    pub audit_log_id: i32,
+    pub entity: String,
+    pub entity_id: String,
+}
+
+impl RecordedChange {
+    /// Attach to the current request's audit row; a no-op outside an audited request
+    pub fn attach(self) {
+        let _ = REQUEST_CHANGES.try_with(|changes| {
+            changes
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(self)
+        });
+    }
+}
+
+/// Changed fields as `{"field": {"before": old, "after": new}}`, or `None` when nothing differs
+///
+/// Fields missing on one side count as `null`, so a creation or deletion
+/// lists every field.
+pub fn diff(before: Option<&Value>, after: Option<&Value>) -> Option<Value> {
+    let fields = |value: Option<&Value>| match value {
+        Some(Value::Object(fields)) => fields.clone(),
+        _ => Map::new(),
+    };
+    let (before, after) = (fields(before), fields(after));
+
+    let mut changed = Map::new();
+    for key in before
+        .keys()
+        .chain(after.keys().filter(|key| !before.contains_key(*key)))
+    {
+        let old = before.get(key).unwrap_or(&Value::Null);
+        let new = after.get(key).unwrap_or(&Value::Null);
+        if old != new {
+            changed.insert(
+                key.clone(),
+                serde_json::json!({ "before": old, "after": new }),
+            );
+        }
+    }
+
+    (!changed.is_empty()).then_some(Value::Object(changed))
+}
+
+/// Run a request handler, collecting the changes it attaches
+pub async fn collect_changes<F: Future>(future: F) -> (F::Output, Vec<RecordedChange>) {
+    let changes = Arc::<Mutex<Vec<RecordedChange>>>::default();
+    let output = REQUEST_CHANGES.scope(Arc::clone(&changes), future).await;
+
+    let changes = std::mem::take(&mut *changes.lock().unwrap_or_else(PoisonError::into_inner));
+
+    (output, changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn diff_lists_only_changed_fields() {
+        let before = json!({ "id": 1, "nickname": "Bob", "avatar": null });
+        let after = json!({ "id": 1, "nickname": "Robert", "avatar": "/a.png" });
+
+        assert_eq!(
+            diff(Some(&before), Some(&after)),
+            Some(json!({
+                "nickname": { "before": "Bob", "after": "Robert" },
+                "avatar": { "before": null, "after": "/a.png" },
+            }))
+        );
+        assert_eq!(diff(Some(&before), Some(&before)), None);
+    }
+
+    #[test]
+    fn diff_treats_missing_sides_as_null() {
+        let model = json!({ "id": 1, "nickname": "Bob" });
+
+        assert_eq!(
+            diff(None, Some(&model)),
+            Some(json!({
+                "id": { "before": null, "after": 1 },
+                "nickname": { "before": null, "after": "Bob" },
+            }))
+        );
+        assert_eq!(
+            diff(Some(&model), None),
+            Some(json!({
+                "id": { "before": 1, "after": null },
+                "nickname": { "before": "Bob", "after": null },
+            }))
+        );
+    }
+
+    #[test]
+    fn snapshots_drop_secrets() {
+        let model = json!({ "id": 1, "password": "$argon2id$...", "token_hash": "abc" });
+
+        let change = EntityChange::created(&model);
+        assert_eq!(change.before, None);
+        assert_eq!(change.after, Some(json!({ "id": 1 })));
+    }
+
+    #[test]
+    fn capture_then_update_or_delete() {
+        let before = json!({ "id": 1, "status": "active" });
+        let after = json!({ "id": 1, "status": "banned" });
+
+        let updated = EntityChange::capture(&before).updated(&after);
+        assert_eq!(updated.before, Some(before.clone()));
+        assert_eq!(updated.after, Some(after));
+
+        let deleted = EntityChange::capture(&before).deleted();
+        assert_eq!(deleted.before, Some(before));
+        assert_eq!(deleted.after, None);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod change;
+pub mod service;
+pub mod sink;
@@ -0,0 +1,237 @@
+use sea_orm::{
+    sea_query::{Expr, SimpleExpr},
+    *,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    common::{RequestContext, errors::Result, jwt::Claims},
+    entity::audit_logs,
+    modules::audit::change::{EntityChange, RecordedChange, diff},
+};
+
+/// Fields never copied into audit snapshots
+const REDACTED_FIELDS: &[&str] = &["password", "token_hash"];
+
+/// Risk classification stored with an audit entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl RiskLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskLevel::Low => "low",
+            RiskLevel::Medium => "medium",
+            RiskLevel::High => "high",
+        }
+    }
+}
+
+/// Business change to record in `audit_logs`
+#[derive(Debug, Clone)]
+pub struct AuditEntry {
+    pub action: String,
+    pub entity: String,
+    pub entity_id: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub reason: Option<String>,
+    pub risk_level: RiskLevel,
+}
+
+impl AuditEntry {
+    /// Create entry for `action` performed on one entity instance
+    pub fn new(action: &str, entity: &str, entity_id: impl ToString) -> Self {
+        Self {
+            action: action.to_string(),
+            entity: entity.to_string(),
+            entity_id: entity_id.to_string(),
+            before: None,
+            after: None,
+            reason: None,
+            risk_level: RiskLevel::Low,
+        }
+    }
+
+    /// Attach state before the change
+    pub fn before(mut self, before: Value) -> Self {
+        self.before = Some(before);
+        self
+    }
+
+    /// Attach state after the change
+    pub fn after(mut self, after: Value) -> Self {
+        self.after = Some(after);
+        self
+    }
+
+    /// Attach snapshots of the changed model
+    pub fn change(mut self, change: EntityChange) -> Self {
+        self.before = change.before;
+        self.after = change.after;
+        self
+    }
+
+    /// Attach operator supplied reason
+    pub fn reason(mut self, reason: Option<String>) -> Self {
+        self.reason = reason;
+        self
+    }
+
+    /// Override default low risk level
+    pub fn risk(mut self, risk_level: RiskLevel) -> Self {
+        self.risk_level = risk_level;
+        self
+    }
+}
+
+/// Serialize a model for audit storage, dropping sensitive fields
+pub fn snapshot<T: Serialize>(model: &T) -> Value {
+    let mut value = serde_json::to_value(model).unwrap_or(Value::Null);
+
+    if let Value::Object(fields) = &mut value {
+        for field in REDACTED_FIELDS {
+            fields.remove(*field);
+        }
+    }
+
+    value
+}
+
+/// Record a change made by the authenticated caller
+///
+/// Pass the transaction performing the change so the audit row commits with it.
+/// The field-level diff is stored on this row; attach the returned reference
+/// once the transaction commits so the request's audit row points at it.
+pub async fn record<C: ConnectionTrait>(
+    db: &C,
+    ctx: &RequestContext,
+    claims: &Claims,
+    entry: AuditEntry,
+) -> Result<RecordedChange> {
+    let now = chrono::Utc::now();
+    let operator_role = claims
+        .role_ids
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let changed_fields = diff(entry.before.as_ref(), entry.after.as_ref());
+
+    let log = audit_logs::ActiveModel {
+        request_id: Set(ctx.request_id.clone()),
+        level: Set("info".to_string()),
+        risk_level: Set(entry.risk_level.as_str().to_string()),
+        entity: Set(entry.entity.clone()),
+        entity_id: Set(entry.entity_id.clone()),
+        action: Set(entry.action),
+        api_path: Set(ctx.path.clone()),
+        http_method: Set(ctx.method.clone()),
+        operator_id: Set(claims.sub),
+        operator_name: Set(Some(claims.username.clone())),
+        operator_role: Set(Some(operator_role)),
+        before: Set(entry.before),
+        after: Set(entry.after),
+        changed_fields: Set(changed_fields),
+        ip_address: Set(ctx.ip_address.clone()),
+        user_agent: Set(ctx.user_agent.clone()),
+        status: Set("success".to_string()),
+        reason: Set(entry.reason),
+        created_at: Set(now.into()),
+        completed_at: Set(Some(now.into())),
+        ..Default::default()
+    };
+
+    let inserted = audit_logs::Entity::insert(log).exec(db).await?;
+
+    Ok(RecordedChange {
+        audit_log_id: inserted.last_insert_id,
+        entity: entry.entity,
+        entity_id: entry.entity_id,
+    })
+}
+
+/// Snapshot and parameter keys holding a user's personal data
+const PERSONAL_FIELDS: &[&str] = &[
+    "email",
+    "new_email",
+    "username",
+    "username_skeleton",
+    "nickname",
+    "avatar",
+    "phone",
+];
+
+/// `column` with the personal data keys removed
+fn without_personal_fields(column: &str) -> SimpleExpr {
+    Expr::cust(format!(
+        "{} - '{{{}}}'::text[]",
+        column,
+        PERSONAL_FIELDS.join(",")
+    ))
+}
+
+/// Strip an erased user's personal data from the audit trail
+///
+/// Rows keep their action, entity, timestamps and operator ID so the trail
+/// stays intact. Rows about the user lose the personal keys of their
+/// snapshots and parameters; rows of the user's own requests lose their
+/// name, network details and the same parameter keys.
+pub async fn scrub_subject<C: ConnectionTrait>(
+    db: &C,
+    user_id: i32,
+    placeholder: &str,
+) -> Result<()> {
+    // Service rows use "user", request rows the "users" path segment
+    audit_logs::Entity::update_many()
+        .col_expr(
+            audit_logs::Column::Before,
+            without_personal_fields("before"),
+        )
+        .col_expr(audit_logs::Column::After, without_personal_fields("after"))
+        .col_expr(
+            audit_logs::Column::ChangedFields,
+            without_personal_fields("changed_fields"),
+        )
+        .col_expr(
+            audit_logs::Column::RequestParams,
+            without_personal_fields("request_params"),
+        )
+        .filter(audit_logs::Column::Entity.is_in(["user", "users"]))
+        .filter(audit_logs::Column::EntityId.eq(user_id.to_string()))
+        .exec(db)
+        .await?;
+
+    audit_logs::Entity::update_many()
+        .col_expr(
+            audit_logs::Column::OperatorName,
+            Expr::value(placeholder.to_string()),
+        )
+        .col_expr(
+            audit_logs::Column::IpAddress,
+            Expr::value(Option::<String>::None),
+        )
+        .col_expr(
+            audit_logs::Column::UserAgent,
+            Expr::value(Option::<String>::None),
+        )
+        .col_expr(
+            audit_logs::Column::RequestParams,
+            without_personal_fields("request_params"),
+        )
+        .col_expr(
+            audit_logs::Column::QueryParams,
+            without_personal_fields("query_params"),
+        )
+        .filter(audit_logs::Column::OperatorId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
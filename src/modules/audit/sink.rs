@@ -0,0 +1,87 @@
+use sea_orm::*;
+use std::time::Duration;
+use tokio::{
+    sync::mpsc::{self, error::TrySendError},
+    task::JoinHandle,
+};
+
+use crate::entity::audit_logs;
+
+/// Entries held in memory while the writer catches up
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// Most rows written by one insert
+const BATCH_SIZE: usize = 100;
+
+/// Longest a queued entry waits before its batch is written
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Handle for queueing audit rows to the background writer
+#[derive(Clone)]
+pub struct AuditSink {
+    tx: mpsc::Sender<audit_logs::ActiveModel>,
+}
+
+impl AuditSink {
+    /// Queue a row without waiting; it is dropped with a warning when the queue is full
+    pub fn push(&self, log: audit_logs::ActiveModel) {
+        match self.tx.try_send(log) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                tracing::warn!("Audit queue full, dropping request audit entry");
+            }
+            Err(TrySendError::Closed(_)) => {
+                tracing::warn!("Audit writer stopped, dropping request audit entry");
+            }
+        }
+    }
+}
+
+/// Background task writing the rows queued through [`AuditSink`]
+pub struct AuditWriter {
+    handle: JoinHandle<()>,
+}
+
+impl AuditWriter {
+    /// Wait until every queued row has been written
+    ///
+    /// The writer stops once all [`AuditSink`] clones are dropped, so call
+    /// this after the server has shut down.
+    pub async fn finish(self) {
+        if let Err(e) = self.handle.await {
+            tracing::error!("Audit writer failed: {}", e);
+        }
+    }
+}
+
+/// Spawn background task writing queued audit rows in batches
+pub fn spawn_audit_writer(db: DatabaseConnection) -> (AuditSink, AuditWriter) {
+    let (tx, mut rx) = mpsc::channel(QUEUE_CAPACITY);
+
+    let handle = tokio::spawn(async move {
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+
+        // Wait for a first entry, then gather more until the batch fills or the interval ends
+        while let Some(log) = rx.recv().await {
+            batch.push(log);
+
+            let deadline = tokio::time::Instant::now() + FLUSH_INTERVAL;
+            while batch.len() < BATCH_SIZE {
+                match tokio::time::timeout_at(deadline, rx.recv()).await {
+                    Ok(Some(log)) => batch.push(log),
+                    Ok(None) | Err(_) => break,
+                }
+            }
+
+            let count = batch.len();
+            if let Err(e) = audit_logs::Entity::insert_many(batch.drain(..))
+                .exec(&db)
+                .await
+            {
+                tracing::error!("Failed to write {} audit entries: {}", count, e);
+            }
+        }
+    });
+
+    (AuditSink { tx }, AuditWriter { handle })
+}
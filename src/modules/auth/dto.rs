@@ -2,10 +2,12 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use validator::Validate;
 
+use crate::{entity::sea_orm_active_enums::UserStatus, modules::role::dto::RoleSummary};
+
 /// Login request payload with validation rules
 #[derive(Debug, Deserialize, Validate, ToSchema)]
 pub struct LoginRequest {
-    /// Username for authentication (3-255 characters)
+    /// Username, email address or verified phone number with country code (3-255 characters)
     #[validate(length(min = 3, max = 255))]
     #[schema(example = "admin")]
     pub username: String,
@@ -71,6 +73,56 @@ pub struct AuthResponse {
     pub user: UserInfo,
 }
 
+/// Outcome of the password step of a login
+///
+/// Accounts with SMS codes enabled get a challenge instead of tokens.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    /// Tokens issued straight away
+    Authenticated(AuthResponse),
+
+    /// Code sent, complete the login at `/api/auth/login/mfa`
+    MfaRequired(MfaChallenge),
+}
+
+/// Second login step awaiting a code sent by SMS
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MfaChallenge {
+    /// Always true, distinguishes the challenge from tokens
+    #[schema(example = true)]
+    pub mfa_required: bool,
+
+    /// Token to send back together with the code
+    #[schema(example = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9...")]
+    pub mfa_token: String,
+
+    /// Channel the code was sent through
+    #[schema(example = "sms")]
+    pub channel: String,
+
+    /// Masked phone number the code was sent to
+    #[schema(example = "+*******0123")]
+    pub phone_hint: String,
+
+    /// Seconds until the code and token expire
+    #[schema(example = 600)]
+    pub expires_in: i64,
+}
+
+/// Second login step with the code sent by SMS
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct VerifyMfaRequest {
+    /// Token from the MFA challenge
+    #[validate(length(min = 1))]
+    pub mfa_token: String,
+
+    /// Code sent by SMS
+    #[validate(length(min = 4, max = 10))]
+    #[schema(example = "123456")]
+    pub code: String,
+}
+
 /// User information included in auth response
 #[derive(Debug, Serialize, ToSchema)]
 pub struct UserInfo {
@@ -94,13 +146,15 @@ pub struct UserInfo {
     #[schema(example = "https://example.com/avatar.jpg")]
     pub avatar: Option<String>,
 
-    /// User role identifier for permissions (optional)
-    #[schema(example = 1)]
-    pub role_id: Option<i32>,
+    /// Roles assigned to the user
+    pub roles: Vec<RoleSummary>,
 
-    /// User status: 1=active, 0=disabled
-    #[schema(example = 1)]
-    pub status: i32,
+    /// Lifecycle status of the account
+    pub status: UserStatus,
+
+    /// Password must be changed before any other request is accepted
+    #[schema(example = false)]
+    pub password_change_required: bool,
 }
 
 /// Password change request
@@ -1,10 +1,13 @@
-use axum::{Json, extract::State};
+use axum::{Extension, Json, extract::State};
 use validator::Validate;
 
 use crate::{
-    common::{AppState, errors::Result, response::success},
+    common::{AppState, RequestContext, errors::Result, jwt::Claims, response::success},
     modules::auth::{
-        dto::{AuthResponse, LoginRequest, RegisterRequest},
+        dto::{
+            AuthResponse, ChangePasswordRequest, LoginRequest, LoginResponse, RegisterRequest,
+            VerifyMfaRequest,
+        },
         service,
     },
 };
@@ -15,7 +18,7 @@ use crate::{
     path = "/api/auth/login",
     request_body = LoginRequest,
     responses(
-        (status = 200, description = "Login successful", body = AuthResponse),
+        (status = 200, description = "Login successful, or SMS code sent when MFA is enabled", body = LoginResponse),
         (status = 401, description = "Invalid credentials"),
         (status = 422, description = "Validation error")
     ),
@@ -23,6 +26,7 @@ use crate::{
 )]
 pub async fn login_handler(
     State(state): State<AppState>,
+    ctx: RequestContext,
     Json(payload): Json<LoginRequest>,
 ) -> Result<Json<impl serde::Serialize>> {
     // Validate request payload
@@ -31,8 +35,52 @@ pub async fn login_handler(
         .map_err(|e| crate::common::errors::AppError::ValidationError(e.to_string()))?;
 
     // Process login
-    let response =
-        service::login(&state.db, payload, &state.jwt_secret, state.jwt_expiration).await?;
+    let response = service::login(
+        &state.db,
+        &state.permission_cache,
+        state.sms.as_ref(),
+        &ctx,
+        payload,
+        &state.jwt_secret,
+        state.jwt_expiration,
+    )
+    .await?;
+
+    Ok(Json(success(response)))
+}
+
+/// Complete an MFA login with the code sent by SMS
+#[utoipa::path(
+    post,
+    path = "/api/auth/login/mfa",
+    request_body = VerifyMfaRequest,
+    responses(
+        (status = 200, description = "Login successful", body = AuthResponse),
+        (status = 400, description = "Invalid or expired code"),
+        (status = 401, description = "Invalid or expired MFA token"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Authentication"
+)]
+pub async fn verify_mfa_handler(
+    State(state): State<AppState>,
+    ctx: RequestContext,
+    Json(payload): Json<VerifyMfaRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| crate::common::errors::AppError::ValidationError(e.to_string()))?;
+
+    let response = service::verify_mfa(
+        &state.db,
+        &state.permission_cache,
+        &ctx,
+        payload,
+        &state.jwt_secret,
+        state.jwt_expiration,
+    )
+    .await?;
 
     Ok(Json(success(response)))
 }
@@ -66,3 +114,36 @@ pub async fn register_handler(
         "message": "Registration successful. Please login."
     }))))
 }
+
+/// HTTP handler for changing the authenticated user's password
+#[utoipa::path(
+    post,
+    path = "/api/auth/change-password",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "Password changed"),
+        (status = 400, description = "New password equals the current one"),
+        (status = 401, description = "Invalid current password"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Authentication",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn change_password_handler(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| crate::common::errors::AppError::ValidationError(e.to_string()))?;
+
+    service::change_password(&state.db, &state.permission_cache, claims.sub, payload).await?;
+
+    Ok(Json(success(serde_json::json!({
+        "message": "Password changed. Please login again."
+    }))))
+}
@@ -2,4 +2,4 @@ pub mod dto;
 pub mod handlers;
 pub mod service;
 
-pub use handlers::{login_handler, register_handler};
+pub use handlers::{change_password_handler, login_handler, register_handler};
@@ -2,45 +2,210 @@ use sea_orm::*;
 
 use crate::{
     common::{
+        RequestContext,
         errors::{AppError, Result},
-        jwt::{Claims, generate_token},
-        password::{hash_password, verify_password},
+        jwt::{Claims, MfaClaims, generate_mfa_token, generate_token, verify_mfa_token},
+        password::{hash_password, validate_password_strength, verify_password},
+        permission_cache::{CachedUser, Invalidation, PermissionCache},
+        sms::SmsProvider,
+    },
+    entity::{roles, sea_orm_active_enums::UserStatus, users},
+    modules::{
+        auth::dto::{
+            AuthResponse, ChangePasswordRequest, LoginRequest, LoginResponse, MfaChallenge,
+            RegisterRequest, UserInfo, VerifyMfaRequest,
+        },
+        role::service::{self as role_service, ROLE_STATUS_ACTIVE},
+        system::service::{self as system_service, DEFAULT_ROLE_SETTING},
+        user::{
+            login_history::{self, METHOD_PASSWORD, METHOD_PASSWORD_SMS},
+            phone,
+            service::{self as user_service, NewUser},
+            username,
+        },
     },
-    entity::{roles, users},
-    modules::auth::dto::{AuthResponse, LoginRequest, RegisterRequest, UserInfo},
 };
 
 /// Handle user login
+///
+/// Accounts with SMS codes enabled are sent a code and get an MFA challenge
+/// to complete with [`verify_mfa`] instead of tokens.
 pub async fn login(
     db: &DatabaseConnection,
+    cache: &PermissionCache,
+    sms: &dyn SmsProvider,
+    ctx: &RequestContext,
     req: LoginRequest,
     jwt_secret: &str,
     jwt_exp: i64,
+) -> Result<LoginResponse> {
+    // Query user by email, phone or username; previous usernames keep working while reserved
+    let user = if req.username.contains('@') {
+        user_service::find_by_email(db, &req.username).await?
+    } else if req.username.trim_start().starts_with('+') {
+        phone::find_by_phone(db, &req.username).await?
+    } else {
+        username::find_by_username(db, &req.username).await?
+    };
+    let user = user.ok_or_else(|| AppError::Unauthorized("Invalid credentials".to_string()))?;
+
+    // Verify password
+    if !verify_password(&req.password, &user.password)? {
+        login_history::record(db, ctx, user.id, METHOD_PASSWORD, Some("invalid_password")).await;
+        return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+    }
+
+    if let Err(e) = ensure_can_login(user.status) {
+        login_history::record(db, ctx, user.id, METHOD_PASSWORD, Some("account_inactive")).await;
+        return Err(e);
+    }
+
+    if let (true, Some(number)) = (user.sms_mfa_enabled, user.phone.as_deref()) {
+        let expires_at = phone::send_login_code(db, sms, &user).await?;
+        let mfa_token =
+            generate_mfa_token(&MfaClaims::new(user.id, expires_at.timestamp()), jwt_secret)?;
+
+        return Ok(LoginResponse::MfaRequired(MfaChallenge {
+            mfa_required: true,
+            mfa_token,
+            channel: "sms".to_string(),
+            phone_hint: phone::mask_phone(number),
+            expires_in: phone::CODE_TTL_MINUTES * 60,
+        }));
+    }
+
+    let response = issue_tokens(db, cache, user, jwt_secret, jwt_exp).await?;
+    login_history::record(db, ctx, response.user.id, METHOD_PASSWORD, None).await;
+
+    Ok(LoginResponse::Authenticated(response))
+}
+
+/// Complete an MFA login with the code sent by SMS
+pub async fn verify_mfa(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    ctx: &RequestContext,
+    req: VerifyMfaRequest,
+    jwt_secret: &str,
+    jwt_exp: i64,
 ) -> Result<AuthResponse> {
-    // Query user
-    let user = users::Entity::find()
-        .filter(users::Column::Username.eq(&req.username))
+    let claims = verify_mfa_token(&req.mfa_token, jwt_secret)?;
+
+    let user = users::Entity::find_by_id(claims.sub)
         .one(db)
         .await?
         .ok_or_else(|| AppError::Unauthorized("Invalid credentials".to_string()))?;
 
-    // Verify password
-    if !verify_password(&req.password, &user.password)? {
-        return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+    // The account may have changed since the password step
+    ensure_can_login(user.status)?;
+
+    if let Err(e) = phone::verify_login_code(db, user.id, &req.code).await {
+        login_history::record(db, ctx, user.id, METHOD_PASSWORD_SMS, Some("invalid_code")).await;
+        return Err(e);
     }
 
-    // Check account status
-    if user.status != 1 {
-        return Err(AppError::Forbidden("Account is disabled".to_string()));
+    let response = issue_tokens(db, cache, user, jwt_secret, jwt_exp).await?;
+    login_history::record(db, ctx, response.user.id, METHOD_PASSWORD_SMS, None).await;
+
+    Ok(response)
+}
+
+/// Refuse login for accounts that are not active
+fn ensure_can_login(status: UserStatus) -> Result<()> {
+    match status {
+        UserStatus::Active => {}
+        UserStatus::PendingVerification => {
+            return Err(AppError::Forbidden("Account is not verified".to_string()));
+        }
+        UserStatus::Suspended => {
+            return Err(AppError::Forbidden("Account is suspended".to_string()));
+        }
+        UserStatus::Banned => {
+            return Err(AppError::Forbidden("Account is banned".to_string()));
+        }
+        UserStatus::Deleted => {
+            return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+        }
     }
 
+    Ok(())
+}
+
+/// Bring token claims in line with the account as it is now
+///
+/// Refuses tokens issued before the account's token version moved on, and
+/// tokens of accounts that can no longer log in. Roles, username and the
+/// forced password change flag are reloaded so assignment changes apply to
+/// tokens already issued. The account state is cached until one of those
+/// changes invalidates it.
+pub async fn refresh_claims(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    claims: &mut Claims,
+) -> Result<()> {
+    let user = match cache.get_user(claims.sub) {
+        Some(user) => user,
+        None => {
+            let generation = cache.generation();
+            let user = load_account(db, claims.sub).await?;
+            cache.insert_user(claims.sub, user, generation)
+        }
+    };
+
+    if user.token_version != claims.token_version || user.deleted {
+        return Err(AppError::Unauthorized("Token has been revoked".to_string()));
+    }
+    ensure_can_login(user.status)?;
+
+    claims.role_ids = user.role_ids;
+    claims.username = user.username;
+    claims.password_change_required = user.password_change_required;
+
+    Ok(())
+}
+
+/// Load the account state tokens are checked against
+async fn load_account(db: &DatabaseConnection, user_id: i32) -> Result<CachedUser> {
+    let user = users::Entity::find_by_id(user_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::Unauthorized("Token has been revoked".to_string()))?;
+
+    let role_ids = role_service::user_roles(db, user.id)
+        .await?
+        .into_iter()
+        .map(|role| role.id)
+        .collect();
+
+    Ok(CachedUser {
+        token_version: user.token_version,
+        status: user.status,
+        deleted: user.deleted_at.is_some(),
+        role_ids,
+        username: user.username,
+        password_change_required: user.password_change_required,
+    })
+}
+
+/// Issue an access token for a user who passed every login step
+async fn issue_tokens(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    user: users::Model,
+    jwt_secret: &str,
+    jwt_exp: i64,
+) -> Result<AuthResponse> {
+    // Load assigned roles for claims and response
+    let roles = role_service::user_roles(db, user.id).await?;
+    let role_ids: Vec<i32> = roles.iter().map(|role| role.id).collect();
+
+    // Refuse users whose roles have all been disabled
+    role_service::ensure_active_roles(db, cache, &role_ids).await?;
+
     // Generate access token
-    let claims = Claims::new_access_token(
-        user.id,
-        user.username.clone(),
-        user.role_id.unwrap_or(0),
-        jwt_exp,
-    );
+    let mut claims = Claims::new_access_token(user.id, user.username.clone(), role_ids, jwt_exp);
+    claims.password_change_required = user.password_change_required;
+    claims.token_version = user.token_version;
     let access_token = generate_token(&claims, jwt_secret)?;
 
     Ok(AuthResponse {
@@ -54,55 +219,81 @@ pub async fn login(
             email: user.email,
             nickname: user.nickname,
             avatar: user.avatar,
-            role_id: user.role_id,
+            roles: roles.into_iter().map(Into::into).collect(),
             status: user.status,
+            password_change_required: user.password_change_required,
         },
     })
 }
 
 /// Handle user registration
 pub async fn register(db: &DatabaseConnection, req: RegisterRequest) -> Result<i32> {
-    // Check username uniqueness
-    let existing_user = users::Entity::find()
-        .filter(users::Column::Username.eq(&req.username))
+    // Get default signup role from system settings
+    let default_role_id: i32 = system_service::require_setting(db, DEFAULT_ROLE_SETTING).await?;
+    let default_role = roles::Entity::find_by_id(default_role_id)
         .one(db)
-        .await?;
+        .await?
+        .ok_or_else(|| AppError::Internal("Default role not found".to_string()))?;
 
-    if existing_user.is_some() {
-        return Err(AppError::Conflict("Username already exists".to_string()));
+    if default_role.status != ROLE_STATUS_ACTIVE {
+        return Err(AppError::Internal("Default role is disabled".to_string()));
     }
 
-    // Check email uniqueness
-    let existing_email = users::Entity::find()
-        .filter(users::Column::Email.eq(&req.email))
-        .one(db)
-        .await?;
+    // Create user together with the default role assignment
+    let txn = db.begin().await?;
 
-    if existing_email.is_some() {
-        return Err(AppError::Conflict("Email already exists".to_string()));
-    }
+    let user = user_service::create_user(
+        &txn,
+        NewUser {
+            username: req.username,
+            email: req.email,
+            password: req.password,
+            nickname: req.nickname,
+            role_ids: vec![default_role.id],
+            password_change_required: false,
+        },
+    )
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(user.id)
+}
 
-    // Get default role (assuming role_id 2 is for regular users)
-    let default_role = roles::Entity::find_by_id(2)
+/// Change the authenticated user's password and clear any forced-change flag
+pub async fn change_password(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    user_id: i32,
+    req: ChangePasswordRequest,
+) -> Result<()> {
+    let user = users::Entity::find_by_id(user_id)
         .one(db)
         .await?
-        .ok_or_else(|| AppError::Internal("Default role not found".to_string()))?;
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
 
-    // Hash password
-    let hashed_password = hash_password(&req.password)?;
-
-    // Create user
-    let new_user = users::ActiveModel {
-        username: Set(req.username),
-        email: Set(req.email),
-        nickname: Set(req.nickname),
-        password: Set(hashed_password),
-        role_id: Set(Some(default_role.id)),
-        status: Set(1),
-        ..Default::default()
-    };
+    // Verify current password
+    if !verify_password(&req.old_password, &user.password)? {
+        return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+    }
+
+    validate_password_strength(&req.new_password)?;
+
+    if verify_password(&req.new_password, &user.password)? {
+        return Err(AppError::BadRequest(
+            "New password must differ from the current one".to_string(),
+        ));
+    }
 
-    let result = users::Entity::insert(new_user).exec(db).await?;
+    let token_version = user.token_version;
+    let mut active: users::ActiveModel = user.into();
+    active.password = Set(hash_password(&req.new_password)?);
+    active.password_change_required = Set(false);
+    // Sign out every token issued with the old password
+    active.token_version = Set(token_version + 1);
+    active.updated_at = Set(chrono::Utc::now().into());
+    active.update(db).await?;
+    cache.invalidate(db, Invalidation::User(user_id)).await?;
 
-    Ok(result.last_insert_id)
+    Ok(())
 }
@@ -1,2 +1,5 @@
+pub mod audit;
 pub mod auth;
+pub mod role;
+pub mod system;
 pub mod user;
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::entity::roles;
+
+/// Role reference embedded in user payloads
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct RoleSummary {
+    /// Unique role identifier
+    #[schema(example = 1)]
+    pub id: i32,
+
+    /// Unique role name
+    #[schema(example = "admin")]
+    pub name: String,
+}
+
+impl From<roles::Model> for RoleSummary {
+    fn from(role: roles::Model) -> Self {
+        Self {
+            id: role.id,
+            name: role.name,
+        }
+    }
+}
+
+/// Request to set or clear a role's parent
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetRoleParentRequest {
+    /// Parent role to inherit permissions from, or null to detach
+    #[schema(example = 2)]
+    pub parent_id: Option<i32>,
+}
+
+/// Request to enable or disable a role
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateRoleStatusRequest {
+    /// Role status: 1=active, 0=disabled
+    #[validate(range(min = 0, max = 1))]
+    #[schema(example = 0)]
+    pub status: i32,
+}
+
+/// Permission inherited from an ancestor role
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InheritedPermission {
+    /// Permission slug
+    #[schema(example = "user:list")]
+    pub slug: String,
+
+    /// Nearest ancestor granting the permission
+    pub inherited_from: RoleSummary,
+}
+
+/// Direct and inherited permissions of a role
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RolePermissionsResponse {
+    /// Role being inspected
+    pub role: RoleSummary,
+
+    /// Ancestor roles, nearest parent first
+    pub ancestors: Vec<RoleSummary>,
+
+    /// Permission slugs granted to the role itself
+    pub direct: Vec<String>,
+
+    /// Permission slugs inherited from ancestors and not granted directly
+    pub inherited: Vec<InheritedPermission>,
+}
+
+/// Outcome of synchronising declared route permissions into the database
+#[derive(Debug, Default, Serialize)]
+pub struct PermissionSyncReport {
+    /// Slugs inserted because they were declared but missing
+    pub created: Vec<String>,
+
+    /// Slugs whose name or API pattern were brought in line with the code
+    pub updated: Vec<String>,
+
+    /// API permission slugs present in the database but no longer declared
+    pub orphaned: Vec<String>,
+
+    /// Newly created slugs granted to the super-admin role
+    pub granted: Vec<String>,
+}
@@ -0,0 +1,112 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+use validator::Validate;
+
+use crate::{
+    common::{AppState, errors::Result, response::success},
+    modules::role::{
+        dto::{
+            RolePermissionsResponse, RoleSummary, SetRoleParentRequest, UpdateRoleStatusRequest,
+        },
+        service,
+    },
+};
+
+/// Get a role's direct and inherited permissions
+#[utoipa::path(
+    get,
+    path = "/api/roles/{id}/permissions",
+    params(
+        ("id" = i32, Path, description = "Role identifier")
+    ),
+    responses(
+        (status = 200, description = "Role permissions retrieved successfully", body = RolePermissionsResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Role not found")
+    ),
+    tag = "Roles",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_role_permissions(
+    State(state): State<AppState>,
+    Path(role_id): Path<i32>,
+) -> Result<Json<impl serde::Serialize>> {
+    let permissions = service::permission_breakdown(&state.db, role_id).await?;
+
+    Ok(Json(success(permissions)))
+}
+
+/// Set or clear the parent a role inherits permissions from
+#[utoipa::path(
+    put,
+    path = "/api/roles/{id}/parent",
+    params(
+        ("id" = i32, Path, description = "Role identifier")
+    ),
+    request_body = SetRoleParentRequest,
+    responses(
+        (status = 200, description = "Role parent updated", body = RoleSummary),
+        (status = 400, description = "Role cannot be its own parent"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Role not found"),
+        (status = 409, description = "Parent would create an inheritance cycle")
+    ),
+    tag = "Roles",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn set_role_parent(
+    State(state): State<AppState>,
+    Path(role_id): Path<i32>,
+    Json(payload): Json<SetRoleParentRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    let role = service::set_parent(
+        &state.db,
+        &state.permission_cache,
+        role_id,
+        payload.parent_id,
+    )
+    .await?;
+
+    Ok(Json(success(role)))
+}
+
+/// Enable or disable a role
+#[utoipa::path(
+    patch,
+    path = "/api/roles/{id}/status",
+    params(
+        ("id" = i32, Path, description = "Role identifier")
+    ),
+    request_body = UpdateRoleStatusRequest,
+    responses(
+        (status = 200, description = "Role status updated", body = RoleSummary),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Role not found"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Roles",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn set_role_status(
+    State(state): State<AppState>,
+    Path(role_id): Path<i32>,
+    Json(payload): Json<UpdateRoleStatusRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| crate::common::errors::AppError::ValidationError(e.to_string()))?;
+
+    let role =
+        service::set_status(&state.db, &state.permission_cache, role_id, payload.status).await?;
+
+    Ok(Json(success(role)))
+}
@@ -0,0 +1,30 @@
+use axum::http::Method;
+
+use crate::middleware::RoutePermission;
+
+/// Inspect a role's direct and inherited permissions
+pub static READ_PERMISSIONS: RoutePermission = RoutePermission {
+    slug: "role:read",
+    name: "View role permissions",
+    method: Method::GET,
+    path: "/roles/:id/permissions",
+};
+
+/// Change the parent a role inherits from
+pub static SET_PARENT: RoutePermission = RoutePermission {
+    slug: "role:update",
+    name: "Change role parent",
+    method: Method::PUT,
+    path: "/roles/:id/parent",
+};
+
+/// Enable or disable a role
+pub static SET_STATUS: RoutePermission = RoutePermission {
+    slug: "role:update_status",
+    name: "Enable or disable role",
+    method: Method::PATCH,
+    path: "/roles/:id/status",
+};
+
+/// Every route permission declared by the role module
+pub static ALL: &[&RoutePermission] = &[&READ_PERMISSIONS, &SET_PARENT, &SET_STATUS];
@@ -0,0 +1,529 @@
+use sea_orm::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    common::{
+        errors::{AppError, Result},
+        jwt::Claims,
+        permission_cache::{CachedRole, Invalidation, PermissionCache, PermissionSet},
+    },
+    entity::{permissions, role_permissions, roles, user_roles},
+    middleware::RoutePermission,
+    modules::role::dto::{
+        InheritedPermission, PermissionSyncReport, RolePermissionsResponse, RoleSummary,
+    },
+};
+
+/// Permission type assigned to rows created from route declarations
+const API_PERMISSION_TYPE: i32 = 3;
+
+/// Role status value for enabled roles
+pub const ROLE_STATUS_ACTIVE: i32 = 1;
+
+/// Resolve a role's status and effective permissions, served from cache when fresh
+pub async fn resolve_role(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    role_id: i32,
+) -> Result<CachedRole> {
+    if let Some(role) = cache.get(role_id) {
+        return Ok(role);
+    }
+
+    let generation = cache.generation();
+    let role = load_role(db, role_id).await?;
+
+    Ok(cache.insert(role_id, role, generation))
+}
+
+/// Resolve effective permission slugs of a role (direct and inherited); disabled roles grant nothing
+pub async fn role_permissions(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    role_id: i32,
+) -> Result<PermissionSet> {
+    Ok(resolve_role(db, cache, role_id).await?.permissions)
+}
+
+/// Refuse users whose roles have all been disabled
+///
+/// Users without any role are left alone; only a disabled assignment locks someone out.
+pub async fn ensure_active_roles(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    role_ids: &[i32],
+) -> Result<()> {
+    if role_ids.is_empty() {
+        return Ok(());
+    }
+
+    for &role_id in role_ids {
+        if resolve_role(db, cache, role_id).await?.active {
+            return Ok(());
+        }
+    }
+
+    Err(AppError::Forbidden("Role is disabled".to_string()))
+}
+
+/// Resolve effective permissions for a user as the union of their roles
+pub async fn user_permissions(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    role_ids: &[i32],
+) -> Result<HashSet<String>> {
+    let mut effective = HashSet::new();
+
+    for &role_id in role_ids {
+        effective.extend(role_permissions(db, cache, role_id).await?.iter().cloned());
+    }
+
+    Ok(effective)
+}
+
+/// Get roles assigned to a user, ordered by role ID
+pub async fn user_roles<C: ConnectionTrait>(db: &C, user_id: i32) -> Result<Vec<roles::Model>> {
+    let roles = roles::Entity::find()
+        .inner_join(user_roles::Entity)
+        .filter(user_roles::Column::UserId.eq(user_id))
+        .order_by_asc(roles::Column::Id)
+        .all(db)
+        .await?;
+
+    Ok(roles)
+}
+
+/// Get role summaries for several users at once, keyed by user ID
+pub async fn roles_for_users<C: ConnectionTrait>(
+    db: &C,
+    user_ids: &[i32],
+) -> Result<HashMap<i32, Vec<RoleSummary>>> {
+    let rows = user_roles::Entity::find()
+        .filter(user_roles::Column::UserId.is_in(user_ids.iter().copied()))
+        .find_also_related(roles::Entity)
+        .order_by_asc(user_roles::Column::RoleId)
+        .all(db)
+        .await?;
+
+    let mut by_user: HashMap<i32, Vec<RoleSummary>> = HashMap::new();
+    for (assignment, role) in rows {
+        if let Some(role) = role {
+            by_user
+                .entry(assignment.user_id)
+                .or_default()
+                .push(role.into());
+        }
+    }
+
+    Ok(by_user)
+}
+
+/// Assign a role to a user, ignoring assignments that already exist
+pub async fn assign_role<C: ConnectionTrait>(db: &C, user_id: i32, role_id: i32) -> Result<()> {
+    let assignment = user_roles::ActiveModel {
+        user_id: Set(user_id),
+        role_id: Set(role_id),
+        created_at: Set(chrono::Utc::now().into()),
+    };
+
+    user_roles::Entity::insert(assignment)
+        .on_conflict(
+            sea_query::OnConflict::columns([
+                user_roles::Column::UserId,
+                user_roles::Column::RoleId,
+            ])
+            .do_nothing()
+            .to_owned(),
+        )
+        .do_nothing()
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Refuse to grant roles carrying permissions the caller does not hold
+///
+/// Counts everything a role could confer, including inherited permissions
+/// and those of disabled roles, so enabling a role later cannot escalate.
+pub async fn ensure_grantable(
+    db: &DatabaseConnection,
+    claims: &Claims,
+    role_ids: &[i32],
+) -> Result<()> {
+    for &role_id in role_ids {
+        let role = find_role(db, role_id).await?;
+        if exceeds_caller(db, claims, &role).await? {
+            return Err(AppError::Forbidden(format!(
+                "Cannot grant role '{}' with permissions you do not hold",
+                role.name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Refuse to manage a user whose roles carry permissions the caller does not hold
+///
+/// Keeps admins from editing, suspending or resetting the password of
+/// anyone more privileged than themselves.
+pub async fn ensure_manageable(
+    db: &DatabaseConnection,
+    claims: &Claims,
+    user_id: i32,
+) -> Result<()> {
+    for role in user_roles(db, user_id).await? {
+        if exceeds_caller(db, claims, &role).await? {
+            return Err(AppError::Forbidden(format!(
+                "Cannot manage a user holding role '{}' with permissions you do not hold",
+                role.name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `role` or one of its ancestors carries a permission the caller lacks
+async fn exceeds_caller(
+    db: &DatabaseConnection,
+    claims: &Claims,
+    role: &roles::Model,
+) -> Result<bool> {
+    let lineage: Vec<i32> = std::iter::once(role.id)
+        .chain(ancestors(db, role).await?.into_iter().map(|a| a.id))
+        .collect();
+
+    Ok(load_permissions_by_role(db, &lineage)
+        .await?
+        .into_values()
+        .flatten()
+        .any(|slug| !claims.has_permission(&slug)))
+}
+
+/// Invalidate every cached role, e.g. after bulk permission changes
+pub async fn invalidate_all_roles(db: &DatabaseConnection, cache: &PermissionCache) -> Result<()> {
+    cache.invalidate(db, Invalidation::All).await
+}
+
+/// Get a role's ancestors, nearest parent first
+pub async fn ancestors<C: ConnectionTrait>(
+    db: &C,
+    role: &roles::Model,
+) -> Result<Vec<roles::Model>> {
+    let mut chain = Vec::new();
+    let mut visited = HashSet::from([role.id]);
+    let mut next = role.parent_id;
+
+    while let Some(parent_id) = next {
+        // Stop on cycles that slipped into the data outside of set_parent
+        if !visited.insert(parent_id) {
+            tracing::warn!("Role hierarchy cycle detected at role {}", parent_id);
+            break;
+        }
+
+        let Some(parent) = roles::Entity::find_by_id(parent_id).one(db).await? else {
+            break;
+        };
+
+        next = parent.parent_id;
+        chain.push(parent);
+    }
+
+    Ok(chain)
+}
+
+/// Show a role's direct permissions separately from those inherited through its parents
+pub async fn permission_breakdown(
+    db: &DatabaseConnection,
+    role_id: i32,
+) -> Result<RolePermissionsResponse> {
+    let role = find_role(db, role_id).await?;
+    let ancestors = ancestors(db, &role).await?;
+
+    let role_ids: Vec<i32> = std::iter::once(role.id)
+        .chain(ancestors.iter().map(|ancestor| ancestor.id))
+        .collect();
+    let mut granted = load_permissions_by_role(db, &role_ids).await?;
+
+    let mut direct: Vec<String> = granted.remove(&role.id).unwrap_or_default();
+    direct.sort();
+
+    // Attribute each inherited slug to the nearest ancestor granting it
+    let mut seen: HashSet<String> = direct.iter().cloned().collect();
+    let mut inherited = Vec::new();
+    for ancestor in &ancestors {
+        let mut slugs = granted.remove(&ancestor.id).unwrap_or_default();
+        slugs.sort();
+
+        for slug in slugs {
+            if seen.insert(slug.clone()) {
+                inherited.push(InheritedPermission {
+                    slug,
+                    inherited_from: ancestor.clone().into(),
+                });
+            }
+        }
+    }
+
+    Ok(RolePermissionsResponse {
+        role: role.into(),
+        ancestors: ancestors.into_iter().map(Into::into).collect(),
+        direct,
+        inherited,
+    })
+}
+
+/// Set or clear a role's parent, rejecting changes that would create a cycle
+pub async fn set_parent(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    role_id: i32,
+    parent_id: Option<i32>,
+) -> Result<RoleSummary> {
+    let role = find_role(db, role_id).await?;
+
+    if let Some(parent_id) = parent_id {
+        if parent_id == role.id {
+            return Err(AppError::BadRequest(
+                "A role cannot be its own parent".to_string(),
+            ));
+        }
+
+        let parent = find_role(db, parent_id).await?;
+        let parent_ancestors = ancestors(db, &parent).await?;
+
+        if parent_ancestors
+            .iter()
+            .any(|ancestor| ancestor.id == role.id)
+        {
+            return Err(AppError::Conflict(format!(
+                "Role '{}' already inherits from '{}'; this parent would create a cycle",
+                parent.name, role.name
+            )));
+        }
+    }
+
+    let mut active: roles::ActiveModel = role.into();
+    active.parent_id = Set(parent_id);
+    active.updated_at = Set(chrono::Utc::now().into());
+    let role = active.update(db).await?;
+
+    // Descendants inherit through this role, so every cached set may be stale
+    invalidate_all_roles(db, cache).await?;
+
+    Ok(role.into())
+}
+
+/// Upsert declared route permissions, report orphaned rows and optionally grant new ones
+///
+/// Orphans are rows carrying an API pattern whose slug is no longer declared;
+/// they are reported rather than deleted so role assignments are not lost.
+pub async fn sync_permissions(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    declared: &[&RoutePermission],
+    super_admin_role: Option<&str>,
+) -> Result<PermissionSyncReport> {
+    let mut seen = HashSet::new();
+    if let Some(duplicate) = declared.iter().find(|p| !seen.insert(p.slug)) {
+        return Err(AppError::Internal(format!(
+            "Permission '{}' is declared by more than one route",
+            duplicate.slug
+        )));
+    }
+
+    let txn = db.begin().await?;
+    let now = chrono::Utc::now();
+    let mut report = PermissionSyncReport::default();
+
+    let mut existing: HashMap<String, permissions::Model> = permissions::Entity::find()
+        .all(&txn)
+        .await?
+        .into_iter()
+        .map(|permission| (permission.slug.clone(), permission))
+        .collect();
+
+    let mut created_ids = Vec::new();
+    for permission in declared {
+        let api = permission.api();
+
+        match existing.remove(permission.slug) {
+            Some(row) => {
+                if row.name != permission.name || row.api.as_deref() != Some(api.as_str()) {
+                    let mut active: permissions::ActiveModel = row.into();
+                    active.name = Set(permission.name.to_string());
+                    active.api = Set(Some(api));
+                    active.updated_at = Set(now.into());
+                    active.update(&txn).await?;
+
+                    report.updated.push(permission.slug.to_string());
+                }
+            }
+            None => {
+                let row = permissions::ActiveModel {
+                    r#type: Set(API_PERMISSION_TYPE),
+                    slug: Set(permission.slug.to_string()),
+                    name: Set(permission.name.to_string()),
+                    parent_id: Set(0),
+                    sort: Set(0),
+                    api: Set(Some(api)),
+                    created_at: Set(now.into()),
+                    updated_at: Set(now.into()),
+                    ..Default::default()
+                }
+                .insert(&txn)
+                .await?;
+
+                created_ids.push(row.id);
+                report.created.push(permission.slug.to_string());
+            }
+        }
+    }
+
+    // Whatever is left with an API pattern used to guard a route that no longer exists
+    report.orphaned = existing
+        .into_values()
+        .filter(|permission| permission.api.is_some())
+        .map(|permission| permission.slug)
+        .collect();
+    report.orphaned.sort();
+
+    if let Some(role_name) = super_admin_role {
+        let role = roles::Entity::find()
+            .filter(roles::Column::Name.eq(role_name))
+            .one(&txn)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Role '{}' not found", role_name)))?;
+
+        if !created_ids.is_empty() {
+            let grants = created_ids
+                .iter()
+                .map(|&permission_id| role_permissions::ActiveModel {
+                    role_id: Set(role.id),
+                    permission_id: Set(permission_id),
+                });
+
+            role_permissions::Entity::insert_many(grants)
+                .on_conflict(
+                    sea_query::OnConflict::columns([
+                        role_permissions::Column::RoleId,
+                        role_permissions::Column::PermissionId,
+                    ])
+                    .do_nothing()
+                    .to_owned(),
+                )
+                .do_nothing()
+                .exec(&txn)
+                .await?;
+
+            report.granted = report.created.clone();
+        }
+    }
+
+    txn.commit().await?;
+
+    // Only new grants change what any role resolves to
+    if !report.granted.is_empty() {
+        invalidate_all_roles(db, cache).await?;
+    }
+
+    tracing::info!(
+        "Permission sync: {} created, {} updated, {} granted",
+        report.created.len(),
+        report.updated.len(),
+        report.granted.len()
+    );
+    for slug in &report.orphaned {
+        tracing::warn!("Permission '{}' is not declared by any route", slug);
+    }
+
+    Ok(report)
+}
+
+/// Enable or disable a role; takes effect immediately for already-issued tokens
+pub async fn set_status(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    role_id: i32,
+    status: i32,
+) -> Result<RoleSummary> {
+    let role = find_role(db, role_id).await?;
+
+    let mut active: roles::ActiveModel = role.into();
+    active.status = Set(status);
+    active.updated_at = Set(chrono::Utc::now().into());
+    let role = active.update(db).await?;
+
+    // Descendants stop inheriting from a disabled role, so flush everything
+    invalidate_all_roles(db, cache).await?;
+
+    Ok(role.into())
+}
+
+/// Find role by ID or fail with NotFound
+async fn find_role<C: ConnectionTrait>(db: &C, role_id: i32) -> Result<roles::Model> {
+    roles::Entity::find_by_id(role_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Role {} not found", role_id)))
+}
+
+/// Load a role's status and effective permissions straight from the database
+///
+/// Missing and disabled roles resolve to inactive with no permissions; disabled
+/// ancestors are skipped so their grants stop flowing down the hierarchy.
+async fn load_role(db: &DatabaseConnection, role_id: i32) -> Result<CachedRole> {
+    let Some(role) = roles::Entity::find_by_id(role_id).one(db).await? else {
+        return Ok(CachedRole::default());
+    };
+
+    if role.status != ROLE_STATUS_ACTIVE {
+        return Ok(CachedRole::default());
+    }
+
+    let role_ids: Vec<i32> = std::iter::once(role.id)
+        .chain(
+            ancestors(db, &role)
+                .await?
+                .into_iter()
+                .filter(|ancestor| ancestor.status == ROLE_STATUS_ACTIVE)
+                .map(|ancestor| ancestor.id),
+        )
+        .collect();
+
+    let permissions: HashSet<String> = load_permissions_by_role(db, &role_ids)
+        .await?
+        .into_values()
+        .flatten()
+        .collect();
+
+    Ok(CachedRole {
+        active: true,
+        permissions: permissions.into(),
+    })
+}
+
+/// Load directly granted permission slugs for several roles, keyed by role ID
+async fn load_permissions_by_role<C: ConnectionTrait>(
+    db: &C,
+    role_ids: &[i32],
+) -> Result<HashMap<i32, Vec<String>>> {
+    let rows = permissions::Entity::find()
+        .select_only()
+        .column(role_permissions::Column::RoleId)
+        .column(permissions::Column::Slug)
+        .inner_join(role_permissions::Entity)
+        .filter(role_permissions::Column::RoleId.is_in(role_ids.iter().copied()))
+        .into_tuple::<(i32, String)>()
+        .all(db)
+        .await?;
+
+    let mut by_role: HashMap<i32, Vec<String>> = HashMap::new();
+    for (role_id, slug) in rows {
+        by_role.entry(role_id).or_default().push(slug);
+    }
+
+    Ok(by_role)
+}
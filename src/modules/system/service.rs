@@ -0,0 +1,54 @@
+use sea_orm::*;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    common::errors::{AppError, Result},
+    entity::system_settings,
+};
+
+/// Setting holding the role ID assigned to self-registered users
+pub const DEFAULT_ROLE_SETTING: &str = "auth.default_role_id";
+
+/// Setting enabling Unicode letters in usernames
+pub const UNICODE_USERNAMES_SETTING: &str = "auth.unicode_usernames";
+
+/// Setting holding the list of usernames nobody may take
+pub const RESERVED_USERNAMES_SETTING: &str = "auth.reserved_usernames";
+
+/// Setting holding the number of days before a scheduled deletion is carried out
+pub const DELETION_GRACE_DAYS_SETTING: &str = "account.deletion_grace_days";
+
+/// Setting holding how many username changes are allowed per change period
+pub const USERNAME_CHANGE_LIMIT_SETTING: &str = "account.username_change_limit";
+
+/// Setting holding the rolling window in days for the username change limit
+pub const USERNAME_CHANGE_PERIOD_DAYS_SETTING: &str = "account.username_change_period_days";
+
+/// Setting holding how many days a previous username stays reserved
+pub const USERNAME_RESERVATION_DAYS_SETTING: &str = "account.username_reservation_days";
+
+/// Read a setting by key, returning None when the row does not exist
+pub async fn get_setting<C, T>(db: &C, key: &str) -> Result<Option<T>>
+where
+    C: ConnectionTrait,
+    T: DeserializeOwned,
+{
+    let Some(setting) = system_settings::Entity::find_by_id(key).one(db).await? else {
+        return Ok(None);
+    };
+
+    serde_json::from_value(setting.value)
+        .map(Some)
+        .map_err(|e| AppError::Internal(format!("Invalid value for setting '{}': {}", key, e)))
+}
+
+/// Read a setting by key, failing when it has not been configured
+pub async fn require_setting<C, T>(db: &C, key: &str) -> Result<T>
+where
+    C: ConnectionTrait,
+    T: DeserializeOwned,
+{
+    get_setting(db, key)
+        .await?
+        .ok_or_else(|| AppError::Internal(format!("Setting '{}' is not configured", key)))
+}
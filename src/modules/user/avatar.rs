@@ -0,0 +1,92 @@
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, Limits, imageops::FilterType};
+use std::io::Cursor;
+
+use crate::common::errors::{AppError, Result};
+
+/// Largest accepted upload in bytes
+pub const MAX_AVATAR_BYTES: usize = 5 * 1024 * 1024;
+
+/// Largest accepted source width or height in pixels
+const MAX_AVATAR_DIMENSION: u32 = 4096;
+
+/// Edge lengths of the square thumbnails generated for every avatar
+pub const AVATAR_SIZES: [u32; 3] = [64, 128, 256];
+
+/// Thumbnail size stored in `users.avatar`
+pub const DEFAULT_AVATAR_SIZE: u32 = 256;
+
+/// Formats accepted as avatar sources
+const ALLOWED_FORMATS: [ImageFormat; 4] = [
+    ImageFormat::Png,
+    ImageFormat::Jpeg,
+    ImageFormat::Gif,
+    ImageFormat::WebP,
+];
+
+/// Square avatar thumbnail encoded as PNG
+pub struct Thumbnail {
+    pub size: u32,
+    pub bytes: Vec<u8>,
+}
+
+/// Storage key of a user's avatar thumbnail
+///
+/// Keys are stable per user and size so the public URL never changes.
+pub fn storage_key(user_id: i32, size: u32) -> String {
+    format!("avatars/{}/{}.png", user_id, size)
+}
+
+/// Decode an uploaded image and render square thumbnails in every size
+///
+/// The format is detected from magic bytes rather than the declared content
+/// type. Re-encoding drops EXIF and other metadata after applying orientation.
+/// CPU bound; call from a blocking task.
+pub fn process(bytes: &[u8]) -> Result<Vec<Thumbnail>> {
+    if bytes.len() > MAX_AVATAR_BYTES {
+        return Err(AppError::BadRequest(format!(
+            "Avatar must not exceed {} MB",
+            MAX_AVATAR_BYTES / 1024 / 1024
+        )));
+    }
+
+    let format = image::guess_format(bytes)
+        .ok()
+        .filter(|format| ALLOWED_FORMATS.contains(format))
+        .ok_or_else(|| {
+            AppError::BadRequest("Avatar must be a PNG, JPEG, GIF or WebP image".to_string())
+        })?;
+
+    let image =
+        decode(bytes, format).map_err(|e| AppError::BadRequest(format!("Invalid image: {}", e)))?;
+
+    AVATAR_SIZES
+        .iter()
+        .map(|&size| {
+            let mut bytes = Vec::new();
+            image
+                .resize_to_fill(size, size, FilterType::Lanczos3)
+                .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+                .map_err(|e| AppError::Internal(format!("Failed to encode avatar: {}", e)))?;
+
+            Ok(Thumbnail { size, bytes })
+        })
+        .collect()
+}
+
+/// Decode with dimension limits guarding against decompression bombs
+fn decode(bytes: &[u8], format: ImageFormat) -> image::ImageResult<DynamicImage> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_AVATAR_DIMENSION);
+    limits.max_image_height = Some(MAX_AVATAR_DIMENSION);
+
+    let mut reader = ImageReader::with_format(Cursor::new(bytes), format);
+    reader.limits(limits);
+
+    let mut decoder = reader.into_decoder()?;
+    let orientation = decoder.orientation()?;
+
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+
+    Ok(image)
+}
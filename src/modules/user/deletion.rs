@@ -0,0 +1,399 @@
+use chrono::Duration;
+use sea_orm::{
+    sea_query::{Expr, LockBehavior, LockType},
+    *,
+};
+use std::sync::Arc;
+
+use crate::{
+    common::{
+        RequestContext,
+        errors::{AppError, Result},
+        jwt::Claims,
+        password::{hash_password, verify_password},
+        permission_cache::{Invalidation, PermissionCache},
+        policy::{Action, authorize},
+        storage::Storage,
+        token::generate_token,
+    },
+    entity::{
+        email_change_requests, intentions, invitations, login_logs, phone_verifications,
+        sea_orm_active_enums::UserStatus, sessions, user_preferences, user_roles, username_history,
+        users,
+    },
+    modules::{
+        audit::{
+            change::EntityChange,
+            service::{self as audit, AuditEntry, RiskLevel},
+        },
+        system::service::{self as system_service, DELETION_GRACE_DAYS_SETTING},
+        user::{
+            avatar,
+            dto::{AccountDeletionResponse, DeleteAccountRequest, UserProfile},
+            export,
+            service::{build_profile, find_user},
+        },
+    },
+};
+
+/// Grace period used when the setting is missing
+const DEFAULT_GRACE_DAYS: i64 = 30;
+
+/// How often the background job looks for accounts due for erasure
+const ERASURE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Reason stored on sessions revoked by erasure
+const ERASURE_REVOKE_REASON: &str = "account_deleted";
+
+/// Schedule deletion of the caller's own account
+pub async fn schedule_own_deletion(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    req: DeleteAccountRequest,
+) -> Result<AccountDeletionResponse> {
+    let user = find_user(db, claims.sub).await?;
+
+    if !verify_password(&req.password, &user.password)? {
+        return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+    }
+
+    schedule(db, ctx, claims, user, req.reason).await
+}
+
+/// Schedule deletion of a user on behalf of an admin
+pub async fn schedule_deletion(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    user_id: i32,
+) -> Result<AccountDeletionResponse> {
+    let user = find_user(db, user_id).await?;
+    authorize(claims, Action::Delete, &user)?;
+
+    schedule(db, ctx, claims, user, None).await
+}
+
+/// Cancel the caller's own scheduled deletion
+pub async fn cancel_own_deletion(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+) -> Result<UserProfile> {
+    let user = find_user(db, claims.sub).await?;
+
+    cancel(db, ctx, claims, user).await
+}
+
+/// Cancel a user's scheduled deletion on behalf of an admin
+pub async fn cancel_deletion(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    user_id: i32,
+) -> Result<UserProfile> {
+    let user = find_user(db, user_id).await?;
+    authorize(claims, Action::Delete, &user)?;
+
+    cancel(db, ctx, claims, user).await
+}
+
+/// Erase every account whose grace period has ended, returning how many were erased
+pub async fn erase_due_accounts(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    storage: &dyn Storage,
+) -> Result<usize> {
+    let due: Vec<i32> = due_accounts(users::Entity::find())
+        .select_only()
+        .column(users::Column::Id)
+        .into_tuple()
+        .all(db)
+        .await?;
+
+    let mut erased = 0;
+    for user_id in due {
+        match erase_user(db, cache, storage, user_id).await {
+            Ok(true) => erased += 1,
+            Ok(false) => {}
+            Err(e) => tracing::error!("Failed to erase account {}: {}", user_id, e),
+        }
+    }
+
+    Ok(erased)
+}
+
+/// Spawn background task erasing accounts once their grace period ends
+pub fn spawn_erasure_job(
+    db: DatabaseConnection,
+    cache: PermissionCache,
+    storage: Arc<dyn Storage>,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ERASURE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match erase_due_accounts(&db, &cache, storage.as_ref()).await {
+                Ok(0) => {}
+                Ok(erased) => tracing::info!("Erased {} deleted account(s)", erased),
+                Err(e) => tracing::error!("Account erasure job failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Set the deletion date after the configured grace period
+async fn schedule(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    user: users::Model,
+    reason: Option<String>,
+) -> Result<AccountDeletionResponse> {
+    if user.deletion_scheduled_at.is_some() {
+        return Err(AppError::Conflict(
+            "Account deletion is already scheduled".to_string(),
+        ));
+    }
+
+    let grace_days = system_service::get_setting(db, DELETION_GRACE_DAYS_SETTING)
+        .await?
+        .unwrap_or(DEFAULT_GRACE_DAYS);
+    let scheduled_at = chrono::Utc::now() + Duration::days(grace_days);
+
+    let change = EntityChange::capture(&user);
+    let mut active: users::ActiveModel = user.into();
+    active.deletion_scheduled_at = Set(Some(scheduled_at.into()));
+    active.updated_at = Set(chrono::Utc::now().into());
+
+    let txn = db.begin().await?;
+    let user = active.update(&txn).await?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.schedule_deletion", "user", user.id)
+            .change(change.updated(&user))
+            .reason(reason)
+            .risk(RiskLevel::High),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    Ok(AccountDeletionResponse {
+        deletion_scheduled_at: scheduled_at,
+    })
+}
+
+/// Clear a pending deletion date
+async fn cancel(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    user: users::Model,
+) -> Result<UserProfile> {
+    if user.deletion_scheduled_at.is_none() {
+        return Err(AppError::Conflict(
+            "No account deletion is scheduled".to_string(),
+        ));
+    }
+
+    let change = EntityChange::capture(&user);
+    let mut active: users::ActiveModel = user.into();
+    active.deletion_scheduled_at = Set(None);
+    active.updated_at = Set(chrono::Utc::now().into());
+
+    let txn = db.begin().await?;
+    let user = active.update(&txn).await?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.cancel_deletion", "user", user.id).change(change.updated(&user)),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    build_profile(db, user).await
+}
+
+/// Narrow a user query to accounts whose grace period has ended
+fn due_accounts(select: Select<users::Entity>) -> Select<users::Entity> {
+    select
+        .filter(users::Column::DeletionScheduledAt.lte(chrono::Utc::now()))
+        .filter(users::Column::DeletedAt.is_null())
+}
+
+/// Anonymize a user in place and scrub personal data from related rows
+///
+/// The row is kept so foreign keys from logs and settings stay valid, and
+/// `audit_logs` rows are scrubbed rather than deleted to preserve the trail.
+/// The account is claimed with `FOR UPDATE SKIP LOCKED`, so concurrent jobs
+/// on other instances skip it; returns `false` when it was claimed elsewhere,
+/// cancelled or already erased.
+async fn erase_user(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    storage: &dyn Storage,
+    user_id: i32,
+) -> Result<bool> {
+    let now = chrono::Utc::now();
+    let placeholder = format!("deleted_{}", user_id);
+
+    let txn = db.begin().await?;
+
+    let Some(user) = due_accounts(users::Entity::find_by_id(user_id))
+        .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+        .one(&txn)
+        .await?
+    else {
+        return Ok(false);
+    };
+    let token_version = user.token_version;
+    let email = user.email.clone();
+
+    let mut active: users::ActiveModel = user.into();
+    active.username = Set(placeholder.clone());
+    active.username_skeleton = Set(None);
+    active.email = Set(format!("{}@deleted.invalid", placeholder));
+    active.nickname = Set(placeholder.clone());
+    // Random hash so the account can never be logged into again
+    active.password = Set(hash_password(&generate_token())?);
+    active.avatar = Set(None);
+    active.phone = Set(None);
+    active.phone_verified_at = Set(None);
+    active.sms_mfa_enabled = Set(false);
+    active.status = Set(UserStatus::Deleted);
+    active.password_change_required = Set(false);
+    active.deletion_scheduled_at = Set(None);
+    active.deleted_at = Set(Some(now.into()));
+    active.token_version = Set(token_version + 1);
+    active.updated_at = Set(now.into());
+    active.update(&txn).await?;
+
+    audit::scrub_subject(&txn, user_id, &placeholder).await?;
+
+    // Contact requests sent from the account's address
+    intentions::Entity::delete_many()
+        .filter(export::intentions_of(&email))
+        .exec(&txn)
+        .await?;
+    user_roles::Entity::delete_many()
+        .filter(user_roles::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    email_change_requests::Entity::delete_many()
+        .filter(email_change_requests::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    phone_verifications::Entity::delete_many()
+        .filter(phone_verifications::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    user_preferences::Entity::delete_many()
+        .filter(user_preferences::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    // Releases any usernames still reserved for the account
+    username_history::Entity::delete_many()
+        .filter(username_history::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    // The accepted invitation still holds the original address
+    invitations::Entity::delete_many()
+        .filter(invitations::Column::AcceptedUserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+
+    // Revoke sessions that are still live
+    sessions::Entity::update_many()
+        .col_expr(sessions::Column::Status, Expr::value("revoked"))
+        .col_expr(sessions::Column::RevokedAt, Expr::value(now.naive_utc()))
+        .col_expr(
+            sessions::Column::RevokedReason,
+            Expr::value(ERASURE_REVOKE_REASON),
+        )
+        .filter(sessions::Column::UserId.eq(user_id))
+        .filter(sessions::Column::RevokedAt.is_null())
+        .exec(&txn)
+        .await?;
+
+    // Scrub network and device identifiers from sessions and login history
+    sessions::Entity::update_many()
+        .col_expr(
+            sessions::Column::IpAddress,
+            Expr::value(Option::<String>::None),
+        )
+        .col_expr(
+            sessions::Column::UserAgent,
+            Expr::value(Option::<String>::None),
+        )
+        .col_expr(
+            sessions::Column::DeviceId,
+            Expr::value(Option::<String>::None),
+        )
+        .filter(sessions::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+    login_logs::Entity::update_many()
+        .col_expr(
+            login_logs::Column::IpAddress,
+            Expr::value(Option::<String>::None),
+        )
+        .col_expr(
+            login_logs::Column::Location,
+            Expr::value(Option::<String>::None),
+        )
+        .col_expr(
+            login_logs::Column::UserAgent,
+            Expr::value(Option::<String>::None),
+        )
+        .col_expr(
+            login_logs::Column::DeviceId,
+            Expr::value(Option::<String>::None),
+        )
+        .filter(login_logs::Column::UserId.eq(user_id))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    // The erasure stands either way; cached state still expires with its TTL
+    if let Err(e) = cache.invalidate(db, Invalidation::User(user_id)).await {
+        tracing::warn!(
+            "Failed to invalidate cached state of erased account {}: {}",
+            user_id,
+            e
+        );
+    }
+
+    // Files cannot be rolled back, so remove them only once the erasure is committed
+    if let Err(e) = export::delete_user_exports(db, storage, user_id).await {
+        tracing::warn!(
+            "Failed to delete exports of erased account {}: {}",
+            user_id,
+            e
+        );
+    }
+    for size in avatar::AVATAR_SIZES {
+        if let Err(e) = storage.delete(&avatar::storage_key(user_id, size)).await {
+            tracing::warn!(
+                "Failed to delete avatar of erased account {}: {}",
+                user_id,
+                e
+            );
+        }
+    }
+
+    tracing::info!("Erased account {}", user_id);
+
+    Ok(true)
+}
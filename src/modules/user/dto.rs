@@ -1,5 +1,11 @@
-use serde::Serialize;
-use utoipa::ToSchema;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use crate::{
+    common::SortOrder, entity::sea_orm_active_enums::UserStatus, modules::role::dto::RoleSummary,
+};
 
 /// User profile response
 #[derive(Debug, Serialize, ToSchema)]
@@ -24,13 +30,24 @@ pub struct UserProfile {
     #[schema(example = "https://example.com/avatar.jpg")]
     pub avatar: Option<String>,
 
-    /// User role identifier for permissions (optional)
-    #[schema(example = 1)]
-    pub role_id: Option<i32>,
+    /// Roles assigned to the user
+    pub roles: Vec<RoleSummary>,
 
-    /// User account status: 1=active, 0=disabled
-    #[schema(example = 1)]
-    pub status: i32,
+    /// Lifecycle status of the account
+    pub status: UserStatus,
+
+    /// When the account will be erased, if deletion has been requested
+    pub deletion_scheduled_at: Option<DateTime<Utc>>,
+
+    /// Verified phone number in E.164 format (optional)
+    #[schema(example = "+14155550123")]
+    pub phone: Option<String>,
+
+    /// Whether login requires a code sent to the verified phone
+    pub sms_mfa_enabled: bool,
+
+    /// Display and notification settings
+    pub preferences: UserPreferences,
 }
 
 /// User list item
@@ -52,11 +69,778 @@ pub struct UserListItem {
     #[schema(example = "Administrator")]
     pub nickname: String,
 
-    /// User role identifier for permissions (optional)
-    #[schema(example = 1)]
+    /// Roles assigned to the user
+    pub roles: Vec<RoleSummary>,
+
+    /// Lifecycle status of the account
+    pub status: UserStatus,
+}
+
+/// Whitelisted sort fields for the user list
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UserSortField {
+    Id,
+    Username,
+    Email,
+    Nickname,
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+}
+
+/// Filters and sorting for the user list
+#[derive(Debug, Default, Serialize, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserListQuery {
+    /// Filter by lifecycle status
+    #[param(inline)]
+    pub status: Option<UserStatus>,
+
+    /// Only users holding this role
+    #[param(example = 2)]
     pub role_id: Option<i32>,
 
-    /// User account status: 1=active, 0=disabled
+    /// Only banned (true) or not banned (false) users
+    pub banned: Option<bool>,
+
+    /// Only users created at or after this time (RFC 3339)
+    pub created_from: Option<DateTime<Utc>>,
+
+    /// Only users created before this time (RFC 3339)
+    pub created_to: Option<DateTime<Utc>>,
+
+    /// Case-insensitive match across username, email and nickname
+    #[validate(length(min = 1, max = 100))]
+    #[param(example = "john")]
+    pub keyword: Option<String>,
+
+    /// Field to sort by
+    #[param(inline)]
+    pub sort_by: Option<UserSortField>,
+
+    /// Sort direction
+    #[param(inline)]
+    pub sort_order: Option<SortOrder>,
+}
+
+/// Admin request to create a user
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateUserRequest {
+    /// Unique username (3-30 characters, alphanumeric and underscore only)
+    #[validate(length(min = 3, max = 30))]
+    #[schema(example = "newuser")]
+    pub username: String,
+
+    /// Valid email address
+    #[validate(email)]
+    #[schema(example = "user@example.com")]
+    pub email: String,
+
+    /// Strong password (6-128 characters)
+    #[validate(length(min = 6, max = 128))]
+    #[schema(example = "password123")]
+    pub password: String,
+
+    /// Display name or full name (2-100 characters)
+    #[validate(length(min = 2, max = 100))]
+    #[schema(example = "John Doe")]
+    pub nickname: String,
+
+    /// Roles to assign to the new user
+    #[validate(length(max = 20))]
+    #[schema(example = json!([2]))]
+    pub role_ids: Vec<i32>,
+
+    /// Require a password change at first login
+    #[serde(default)]
+    #[schema(example = true)]
+    pub password_change_required: bool,
+}
+
+/// Admin request to update user attributes; omitted fields are left unchanged
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateUserRequest {
+    /// New email address
+    #[validate(email)]
+    #[schema(example = "user@example.com")]
+    pub email: Option<String>,
+
+    /// New display name (2-100 characters)
+    #[validate(length(min = 2, max = 100))]
+    #[schema(example = "John Doe")]
+    pub nickname: Option<String>,
+
+    /// New avatar URL
+    #[validate(url, length(max = 255))]
+    #[schema(example = "https://example.com/avatar.jpg")]
+    pub avatar: Option<String>,
+}
+
+/// Admin request replacing a user's roles
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct AssignRolesRequest {
+    /// Complete set of roles the user should hold
+    #[validate(length(max = 20))]
+    #[schema(example = json!([2, 3]))]
+    pub role_ids: Vec<i32>,
+}
+
+/// Admin request to move a user to another lifecycle status
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateUserStatusRequest {
+    /// Target status; deletion goes through the account deletion flow
+    #[schema(example = "suspended")]
+    pub status: UserStatus,
+
+    /// Reason recorded in the audit log
+    #[validate(length(max = 500))]
+    #[schema(example = "Requested by account owner")]
+    pub reason: Option<String>,
+}
+
+/// Temporary credentials issued by an admin password reset
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PasswordResetResponse {
+    /// One-time password the user must change at next login
+    #[schema(example = "x7!Kq2mP#rT9wZ4a")]
+    pub temporary_password: String,
+}
+
+/// Self-service profile update; omitted fields are left unchanged
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateProfileRequest {
+    /// New display name (2-100 characters)
+    #[validate(length(min = 2, max = 100))]
+    #[schema(example = "John Doe")]
+    pub nickname: Option<String>,
+
+    /// New avatar URL
+    #[validate(url, length(max = 255))]
+    #[schema(example = "https://example.com/avatar.jpg")]
+    pub avatar: Option<String>,
+}
+
+/// Request to change the caller's email address
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ChangeEmailRequest {
+    /// New email address; a verification token is sent here
+    #[validate(email, length(max = 255))]
+    #[schema(example = "new@example.com")]
+    pub new_email: String,
+
+    /// Current password confirming the request
+    #[validate(length(min = 1))]
+    #[schema(example = "password123")]
+    pub password: String,
+}
+
+/// Request to change the caller's username
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ChangeUsernameRequest {
+    /// New username (3-30 characters, alphanumeric and underscore only)
+    #[validate(length(min = 3, max = 30))]
+    #[schema(example = "new_name")]
+    pub username: String,
+
+    /// Current password confirming the request
+    #[validate(length(min = 1))]
+    #[schema(example = "password123")]
+    pub password: String,
+}
+
+/// Confirmation of a pending email change
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ConfirmEmailChangeRequest {
+    /// Token sent to the new email address
+    #[validate(length(min = 1, max = 128))]
+    #[schema(example = "3f9c2a...")]
+    pub token: String,
+}
+
+/// Pending email change awaiting verification
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EmailChangePending {
+    /// Address that must be verified before the change takes effect
+    #[schema(example = "new@example.com")]
+    pub new_email: String,
+
+    /// When the verification token expires
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Request to add or replace the caller's phone number
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct AddPhoneRequest {
+    /// Phone number including country code
+    #[validate(length(min = 3, max = 32))]
+    #[schema(example = "+14155550123")]
+    pub phone: String,
+
+    /// Current password confirming the request
+    #[validate(length(min = 1))]
+    #[schema(example = "password123")]
+    pub password: String,
+}
+
+/// One-time code confirming a pending phone number
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct VerifyPhoneRequest {
+    /// Code sent by SMS
+    #[validate(length(min = 4, max = 10))]
+    #[schema(example = "123456")]
+    pub code: String,
+}
+
+/// Request to remove the caller's phone number
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct RemovePhoneRequest {
+    /// Current password confirming the request
+    #[validate(length(min = 1))]
+    #[schema(example = "password123")]
+    pub password: String,
+}
+
+/// Request to turn SMS login codes on or off
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateSmsMfaRequest {
+    /// Require a code sent to the verified phone at login
+    pub enabled: bool,
+
+    /// Current password confirming the request
+    #[validate(length(min = 1))]
+    #[schema(example = "password123")]
+    pub password: String,
+}
+
+/// Phone number awaiting code verification
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PhoneVerificationPending {
+    /// Normalized number the code was sent to
+    #[schema(example = "+14155550123")]
+    pub phone: String,
+
+    /// When the code expires
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Multipart form for an avatar upload
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct AvatarUpload {
+    /// PNG, JPEG, GIF or WebP image, at most 5 MB
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+/// Generated avatar thumbnail
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AvatarThumbnail {
+    /// Edge length in pixels
+    #[schema(example = 128)]
+    pub size: u32,
+
+    /// Public URL of the thumbnail
+    #[schema(example = "/uploads/avatars/1/128.png")]
+    pub url: String,
+}
+
+/// Result of an avatar upload
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AvatarResponse {
+    /// Avatar URL stored on the profile
+    #[schema(example = "/uploads/avatars/1/256.png")]
+    pub avatar: String,
+
+    /// Every generated thumbnail size
+    pub thumbnails: Vec<AvatarThumbnail>,
+}
+
+/// Request to delete the caller's own account
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct DeleteAccountRequest {
+    /// Current password confirming the request
+    #[validate(length(min = 1))]
+    #[schema(example = "password123")]
+    pub password: String,
+
+    /// Optional reason recorded in the audit log
+    #[validate(length(max = 500))]
+    #[schema(example = "No longer using the service")]
+    pub reason: Option<String>,
+}
+
+/// Scheduled account deletion
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountDeletionResponse {
+    /// When the account will be erased unless the deletion is cancelled
+    pub deletion_scheduled_at: DateTime<Utc>,
+}
+
+/// Personal data export
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DataExportResponse {
+    /// Export identifier
     #[schema(example = 1)]
-    pub status: i32,
+    pub id: i32,
+
+    /// One of pending, processing, ready, failed or expired
+    #[schema(example = "ready")]
+    pub status: String,
+
+    /// When the export was requested
+    pub created_at: DateTime<Utc>,
+
+    /// When the archive was built
+    pub completed_at: Option<DateTime<Utc>>,
+
+    /// When the archive will be removed
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// Short-lived link to the archive, present once it is ready
+    #[schema(example = "/api/exports/download?token=eyJ0eXAiOiJKV1Qi...")]
+    pub download_url: Option<String>,
+}
+
+/// Query carrying a signed download token
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DownloadQuery {
+    /// Token from a download link
+    pub token: String,
+}
+
+/// How imported users receive their initial credentials
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialDelivery {
+    /// Email each address an invitation to create their own account; only `email` and `role` are used
+    #[default]
+    Invitation,
+
+    /// Return generated temporary passwords in the response
+    TemporaryPassword,
+}
+
+/// Options for a CSV user import
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportUsersQuery {
+    /// Validate only and report per-row errors without creating anyone
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// How new users receive their credentials
+    #[param(inline)]
+    #[serde(default)]
+    pub delivery: CredentialDelivery,
+}
+
+/// CSV file upload for a user import
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct UserImportUpload {
+    /// CSV with header `username,email,nickname,role`; empty role means the default signup role
+    ///
+    /// Invitation delivery only needs the `email` column, since invitees pick
+    /// their own username.
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
+
+/// Problem found in one CSV row
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportRowError {
+    /// Line number in the CSV file, counting the header as line 1
+    #[schema(example = 3)]
+    pub line: u64,
+
+    /// Every problem with the row
+    #[schema(example = json!(["Email already exists"]))]
+    pub errors: Vec<String>,
+}
+
+/// User created by an import
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportedUser {
+    /// Line number in the CSV file
+    #[schema(example = 2)]
+    pub line: u64,
+
+    /// ID of the new user
+    #[schema(example = 42)]
+    pub id: i32,
+
+    /// Username of the new user
+    #[schema(example = "newuser")]
+    pub username: String,
+
+    /// Temporary password to hand to the user
+    #[schema(example = "x7!Kq2mP#rT9wZ4a")]
+    pub temporary_password: String,
+}
+
+/// Invitation sent by an import
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportedInvitation {
+    /// Line number in the CSV file
+    #[schema(example = 2)]
+    pub line: u64,
+
+    /// ID of the invitation
+    #[schema(example = 7)]
+    pub id: i32,
+
+    /// Invited address
+    #[schema(example = "new.user@example.com")]
+    pub email: String,
+}
+
+/// Outcome of a CSV user import
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportReport {
+    /// Whether this was a validation-only run
+    pub dry_run: bool,
+
+    /// Data rows in the file
+    #[schema(example = 120)]
+    pub total_rows: usize,
+
+    /// Rows that passed validation
+    #[schema(example = 118)]
+    pub valid_rows: usize,
+
+    /// Users created with `temporary_password` delivery; empty for dry runs
+    pub created: Vec<ImportedUser>,
+
+    /// Invitations sent with `invitation` delivery; empty for dry runs
+    pub invited: Vec<ImportedInvitation>,
+
+    /// Rows skipped because of validation errors or failures while creating them
+    pub errors: Vec<ImportRowError>,
+}
+
+/// Spreadsheet format of a user export
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Xlsx,
+}
+
+/// Format of a user export; filters are shared with the user list
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserExportQuery {
+    /// Spreadsheet format
+    #[param(inline)]
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Fuzzy user search query
+#[derive(Debug, Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserSearchQuery {
+    /// Text matched against username, email and nickname, tolerating typos; at least 2 characters once trimmed
+    #[validate(length(min = 2, max = 100))]
+    #[param(example = "jonh")]
+    pub q: String,
+
+    /// Maximum number of results (1-50)
+    #[validate(range(min = 1, max = 50))]
+    #[param(example = 20)]
+    pub limit: Option<u64>,
+}
+
+/// Part of a field that matched the search text
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchHighlight {
+    /// Field containing the match: username, email or nickname
+    #[schema(example = "username")]
+    pub field: &'static str,
+
+    /// Character offset where the match starts
+    #[schema(example = 0)]
+    pub start: usize,
+
+    /// Character offset just past the match
+    #[schema(example = 4)]
+    pub end: usize,
+}
+
+/// User search hit ranked by similarity
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserSearchResult {
+    /// Unique user identifier
+    #[schema(example = 1)]
+    pub id: i32,
+
+    /// Username for login
+    #[schema(example = "john")]
+    pub username: String,
+
+    /// User email address
+    #[schema(example = "john@example.com")]
+    pub email: String,
+
+    /// User display name
+    #[schema(example = "John Doe")]
+    pub nickname: String,
+
+    /// User avatar URL (optional)
+    #[schema(example = "https://example.com/avatar.jpg")]
+    pub avatar: Option<String>,
+
+    /// Lifecycle status of the account
+    pub status: UserStatus,
+
+    /// Best word similarity across the searched fields, from 0 to 1
+    #[schema(example = 0.8)]
+    pub score: f32,
+
+    /// Exact occurrences of the search terms within each field
+    pub highlights: Vec<SearchHighlight>,
+}
+
+/// Admin request to invite someone by email
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateInvitationRequest {
+    /// Address the invitation is sent to
+    #[validate(email)]
+    #[schema(example = "new.colleague@example.com")]
+    pub email: String,
+
+    /// Role the account receives on acceptance
+    #[schema(example = 2)]
+    pub role_id: i32,
+}
+
+/// One login attempt in a user's history
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LoginHistoryItem {
+    /// Login log identifier
+    #[schema(example = 1)]
+    pub id: i32,
+
+    /// When the attempt was made
+    pub login_at: Option<DateTime<Utc>>,
+
+    /// Client IP address
+    #[schema(example = "203.0.113.7")]
+    pub ip_address: Option<String>,
+
+    /// Approximate location, when known
+    #[schema(example = "Berlin, DE")]
+    pub location: Option<String>,
+
+    /// Browser and operating system derived from the user agent
+    #[schema(example = "Chrome on Windows 10")]
+    pub device: Option<String>,
+
+    /// Raw client user agent
+    pub user_agent: Option<String>,
+
+    /// How the user authenticated
+    #[schema(example = "password")]
+    pub method: Option<String>,
+
+    /// Outcome of the attempt
+    #[schema(example = "success")]
+    pub status: String,
+
+    /// Why a failed attempt was refused
+    #[schema(example = "invalid_password")]
+    pub fail_reason: Option<String>,
+
+    /// Risk assessed at login time
+    #[schema(example = "low")]
+    pub risk_level: Option<String>,
+
+    /// First login from this device
+    pub is_new_device: bool,
+
+    /// First login from this IP address
+    pub is_new_location: bool,
+
+    /// High-risk login, or a failure from an unfamiliar device or location
+    pub suspicious: bool,
+}
+
+/// Invitation awaiting acceptance
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InvitationResponse {
+    /// Invitation identifier
+    #[schema(example = 1)]
+    pub id: i32,
+
+    /// Invited email address
+    #[schema(example = "new.colleague@example.com")]
+    pub email: String,
+
+    /// Role the account receives on acceptance
+    pub role: RoleSummary,
+
+    /// Admin who sent the invitation
+    #[schema(example = 1)]
+    pub invited_by: Option<i32>,
+
+    /// When the current token stops working
+    pub expires_at: DateTime<Utc>,
+
+    /// Whether the token has already expired and needs a resend
+    #[schema(example = false)]
+    pub expired: bool,
+
+    /// When the invitation email was last sent
+    pub last_sent_at: DateTime<Utc>,
+
+    /// When the invitation was created
+    pub created_at: DateTime<Utc>,
+}
+
+/// Invitee's choice of credentials when accepting an invitation
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct AcceptInvitationRequest {
+    /// Token from the invitation email
+    #[validate(length(min = 1))]
+    pub token: String,
+
+    /// Unique username (3-30 characters, alphanumeric and underscore only)
+    #[validate(length(min = 3, max = 30))]
+    #[schema(example = "newuser")]
+    pub username: String,
+
+    /// Strong password (6-128 characters)
+    #[validate(length(min = 6, max = 128))]
+    #[schema(example = "password123")]
+    pub password: String,
+
+    /// Display name (2-100 characters); defaults to the username
+    #[validate(length(min = 2, max = 100))]
+    #[schema(example = "John Doe")]
+    pub nickname: Option<String>,
+}
+
+/// Colour scheme used by clients
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    Dark,
+    /// Follow the operating system setting
+    #[default]
+    System,
+}
+
+/// Supported date layouts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum DateFormat {
+    #[default]
+    #[serde(rename = "YYYY-MM-DD")]
+    YearMonthDay,
+    #[serde(rename = "DD/MM/YYYY")]
+    DayMonthYear,
+    #[serde(rename = "MM/DD/YYYY")]
+    MonthDayYear,
+    #[serde(rename = "DD.MM.YYYY")]
+    DayMonthYearDotted,
+}
+
+/// Email notification opt-ins
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct NotificationPreferences {
+    /// Optional security alerts such as sign-ins from new devices; not emailed by the server yet
+    ///
+    /// Notices that guard against account takeover, like email change
+    /// warnings, are sent regardless.
+    #[schema(example = true)]
+    pub security_alerts: bool,
+
+    /// Changes made to the account by administrators; not emailed by the server yet
+    #[schema(example = true)]
+    pub account_updates: bool,
+
+    /// Product announcements and newsletters; not emailed by the server yet
+    #[schema(example = false)]
+    pub product_news: bool,
+}
+
+impl Default for NotificationPreferences {
+    fn default() -> Self {
+        Self {
+            security_alerts: true,
+            account_updates: true,
+            product_news: false,
+        }
+    }
+}
+
+/// Per-user display and notification settings; missing keys take their defaults
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct UserPreferences {
+    /// BCP 47 language tag
+    #[schema(example = "en-US")]
+    pub locale: String,
+
+    /// IANA time zone name
+    #[schema(example = "Europe/Berlin")]
+    pub timezone: String,
+
+    /// Layout for rendered dates
+    pub date_format: DateFormat,
+
+    /// Client colour scheme
+    pub theme: Theme,
+
+    /// Email notification opt-ins
+    pub notifications: NotificationPreferences,
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        Self {
+            locale: "en-US".to_string(),
+            timezone: "UTC".to_string(),
+            date_format: DateFormat::default(),
+            theme: Theme::default(),
+            notifications: NotificationPreferences::default(),
+        }
+    }
+}
+
+/// Partial update of notification opt-ins; omitted fields are left unchanged
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateNotificationPreferences {
+    pub security_alerts: Option<bool>,
+    pub account_updates: Option<bool>,
+    pub product_news: Option<bool>,
+}
+
+/// Partial update of the caller's preferences; omitted fields are left unchanged
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdatePreferencesRequest {
+    /// BCP 47 language tag
+    #[validate(length(min = 2, max = 35))]
+    #[schema(example = "de-DE")]
+    pub locale: Option<String>,
+
+    /// IANA time zone name
+    #[validate(length(min = 1, max = 64))]
+    #[schema(example = "Europe/Berlin")]
+    pub timezone: Option<String>,
+
+    /// Layout for rendered dates
+    pub date_format: Option<DateFormat>,
+
+    /// Client colour scheme
+    pub theme: Option<Theme>,
+
+    /// Email notification opt-ins
+    pub notifications: Option<UpdateNotificationPreferences>,
 }
@@ -0,0 +1,449 @@
+use chrono::Duration;
+use sea_orm::{
+    sea_query::{Expr, SimpleExpr},
+    *,
+};
+use serde_json::Value;
+use std::{
+    io::{Cursor, Write},
+    sync::Arc,
+};
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    common::{
+        RequestContext,
+        errors::{AppError, Result},
+        jwt::{Claims, DownloadClaims, generate_download_token, verify_download_token},
+        storage::Storage,
+        token::generate_token,
+    },
+    entity::{audit_logs, data_exports, intentions, login_logs, sessions, username_history},
+    modules::{
+        audit::service::{self as audit, AuditEntry},
+        role::service as role_service,
+        user::{dto::DataExportResponse, preferences, service::find_user},
+    },
+};
+
+/// Export queued for the background job
+pub const EXPORT_STATUS_PENDING: &str = "pending";
+
+/// Archive is being built
+pub const EXPORT_STATUS_PROCESSING: &str = "processing";
+
+/// Archive can be downloaded
+pub const EXPORT_STATUS_READY: &str = "ready";
+
+/// Building the archive failed
+pub const EXPORT_STATUS_FAILED: &str = "failed";
+
+/// Archive was removed after the retention period
+pub const EXPORT_STATUS_EXPIRED: &str = "expired";
+
+/// How long a built archive is kept
+const EXPORT_RETENTION_DAYS: i64 = 7;
+
+/// How long a single download link stays valid
+const DOWNLOAD_LINK_TTL_MINUTES: i64 = 15;
+
+/// Processing exports older than this are assumed abandoned and requeued
+const STALE_PROCESSING_MINUTES: i64 = 30;
+
+/// How often the background job looks for queued and expired exports
+const EXPORT_JOB_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Session fields never written to an export archive
+const SESSION_SECRET_FIELDS: &[&str] = &["token_hash", "refresh_token_hash"];
+
+/// Queue a data export for the caller and start building it in the background
+pub async fn request_export(
+    db: &DatabaseConnection,
+    storage: Arc<dyn Storage>,
+    ctx: &RequestContext,
+    claims: &Claims,
+    jwt_secret: &str,
+) -> Result<DataExportResponse> {
+    let in_progress = data_exports::Entity::find()
+        .filter(data_exports::Column::UserId.eq(claims.sub))
+        .filter(
+            data_exports::Column::Status.is_in([EXPORT_STATUS_PENDING, EXPORT_STATUS_PROCESSING]),
+        )
+        .count(db)
+        .await?;
+    if in_progress > 0 {
+        return Err(AppError::Conflict(
+            "A data export is already in progress".to_string(),
+        ));
+    }
+
+    let txn = db.begin().await?;
+
+    let export = data_exports::ActiveModel {
+        user_id: Set(claims.sub),
+        status: Set(EXPORT_STATUS_PENDING.to_string()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.request_export", "data_export", export.id),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    // Start right away; the periodic job picks the export up if this task dies
+    let (task_db, export_id) = (db.clone(), export.id);
+    tokio::spawn(async move {
+        if let Err(e) = process_export(&task_db, storage.as_ref(), export_id).await {
+            tracing::error!("Data export {} failed: {}", export_id, e);
+        }
+    });
+
+    to_response(export, jwt_secret)
+}
+
+/// Get one of the caller's exports with a fresh download link when ready
+pub async fn get_export(
+    db: &DatabaseConnection,
+    claims: &Claims,
+    export_id: i32,
+    jwt_secret: &str,
+) -> Result<DataExportResponse> {
+    let export = data_exports::Entity::find_by_id(export_id)
+        .filter(data_exports::Column::UserId.eq(claims.sub))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Export not found".to_string()))?;
+
+    to_response(export, jwt_secret)
+}
+
+/// Resolve a download token to the archive bytes and file name
+pub async fn download_export(
+    db: &DatabaseConnection,
+    storage: &dyn Storage,
+    token: &str,
+    jwt_secret: &str,
+) -> Result<(String, Vec<u8>)> {
+    let link = verify_download_token(token, jwt_secret)
+        .map_err(|_| AppError::Unauthorized("Invalid or expired download link".to_string()))?;
+
+    let export = data_exports::Entity::find_by_id(link.sub)
+        .filter(data_exports::Column::UserId.eq(link.user_id))
+        .filter(data_exports::Column::Status.eq(EXPORT_STATUS_READY))
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Export not found".to_string()))?;
+
+    let key = export
+        .storage_key
+        .ok_or_else(|| AppError::NotFound("Export not found".to_string()))?;
+    let bytes = storage.get(&key).await?;
+
+    Ok((format!("data-export-{}.zip", export.id), bytes))
+}
+
+/// Remove every export of a user along with its archive
+pub async fn delete_user_exports<C: ConnectionTrait>(
+    db: &C,
+    storage: &dyn Storage,
+    user_id: i32,
+) -> Result<()> {
+    let exports = data_exports::Entity::find()
+        .filter(data_exports::Column::UserId.eq(user_id))
+        .all(db)
+        .await?;
+
+    for key in exports
+        .iter()
+        .filter_map(|export| export.storage_key.as_deref())
+    {
+        storage.delete(key).await?;
+    }
+
+    data_exports::Entity::delete_many()
+        .filter(data_exports::Column::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    Ok(())
+}
+
+/// Spawn background task building queued exports and removing expired archives
+pub fn spawn_export_job(db: DatabaseConnection, storage: Arc<dyn Storage>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EXPORT_JOB_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = run_export_job(&db, storage.as_ref()).await {
+                tracing::error!("Data export job failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Requeue abandoned exports, build queued ones and expire old archives
+async fn run_export_job(db: &DatabaseConnection, storage: &dyn Storage) -> Result<()> {
+    let now = chrono::Utc::now();
+
+    data_exports::Entity::update_many()
+        .col_expr(
+            data_exports::Column::Status,
+            Expr::value(EXPORT_STATUS_PENDING),
+        )
+        .filter(data_exports::Column::Status.eq(EXPORT_STATUS_PROCESSING))
+        .filter(
+            data_exports::Column::StartedAt.lt(now - Duration::minutes(STALE_PROCESSING_MINUTES)),
+        )
+        .exec(db)
+        .await?;
+
+    let pending = data_exports::Entity::find()
+        .filter(data_exports::Column::Status.eq(EXPORT_STATUS_PENDING))
+        .all(db)
+        .await?;
+    for export in pending {
+        if let Err(e) = process_export(db, storage, export.id).await {
+            tracing::error!("Data export {} failed: {}", export.id, e);
+        }
+    }
+
+    let expired = data_exports::Entity::find()
+        .filter(data_exports::Column::Status.eq(EXPORT_STATUS_READY))
+        .filter(data_exports::Column::ExpiresAt.lte(now))
+        .all(db)
+        .await?;
+    for export in expired {
+        if let Some(key) = &export.storage_key {
+            storage.delete(key).await?;
+        }
+
+        let mut active: data_exports::ActiveModel = export.into();
+        active.status = Set(EXPORT_STATUS_EXPIRED.to_string());
+        active.storage_key = Set(None);
+        active.update(db).await?;
+    }
+
+    Ok(())
+}
+
+/// Claim a pending export, build its archive and record the outcome
+async fn process_export(
+    db: &DatabaseConnection,
+    storage: &dyn Storage,
+    export_id: i32,
+) -> Result<()> {
+    // Claim atomically so concurrent workers never build the same export
+    let claimed = data_exports::Entity::update_many()
+        .col_expr(
+            data_exports::Column::Status,
+            Expr::value(EXPORT_STATUS_PROCESSING),
+        )
+        .col_expr(
+            data_exports::Column::StartedAt,
+            Expr::value(chrono::Utc::now().fixed_offset()),
+        )
+        .filter(data_exports::Column::Id.eq(export_id))
+        .filter(data_exports::Column::Status.eq(EXPORT_STATUS_PENDING))
+        .exec(db)
+        .await?;
+    if claimed.rows_affected == 0 {
+        return Ok(());
+    }
+
+    let export = data_exports::Entity::find_by_id(export_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Export not found".to_string()))?;
+
+    let result = build_archive(db, export.user_id).await;
+
+    let now = chrono::Utc::now();
+    let mut active: data_exports::ActiveModel = export.clone().into();
+    match result {
+        Ok(archive) => {
+            // Random key so the archive cannot be guessed from the export ID
+            let key = format!("exports/{}/{}.zip", export.user_id, generate_token());
+            storage.put(&key, archive).await?;
+
+            active.status = Set(EXPORT_STATUS_READY.to_string());
+            active.storage_key = Set(Some(key));
+            active.completed_at = Set(Some(now.into()));
+            active.expires_at = Set(Some((now + Duration::days(EXPORT_RETENTION_DAYS)).into()));
+        }
+        Err(e) => {
+            tracing::error!("Failed to build data export {}: {}", export.id, e);
+
+            active.status = Set(EXPORT_STATUS_FAILED.to_string());
+            active.error = Set(Some(e.to_string()));
+            active.completed_at = Set(Some(now.into()));
+        }
+    }
+    active.update(db).await?;
+
+    Ok(())
+}
+
+/// Collect everything stored about a user into a zip of JSON documents
+async fn build_archive(db: &DatabaseConnection, user_id: i32) -> Result<Vec<u8>> {
+    let user = find_user(db, user_id).await?;
+    let roles = role_service::user_roles(db, user_id).await?;
+
+    let login_history = login_logs::Entity::find()
+        .filter(login_logs::Column::UserId.eq(user_id))
+        .order_by_asc(login_logs::Column::Id)
+        .all(db)
+        .await?;
+    let sessions = sessions::Entity::find()
+        .filter(sessions::Column::UserId.eq(user_id))
+        .order_by_asc(sessions::Column::Id)
+        .all(db)
+        .await?;
+    let username_history = username_history::Entity::find()
+        .filter(username_history::Column::UserId.eq(user_id))
+        .order_by_asc(username_history::Column::Id)
+        .all(db)
+        .await?;
+    let audit_entries: Vec<audit_logs::Model> = audit_logs::Entity::find()
+        .filter(audit_logs::Column::OperatorId.eq(user_id))
+        .order_by_asc(audit_logs::Column::Id)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|entry| strip_other_subjects(entry, user_id))
+        .collect();
+    let intentions = intentions::Entity::find()
+        .filter(intentions_of(&user.email))
+        .order_by_asc(intentions::Column::Id)
+        .all(db)
+        .await?;
+
+    let preferences = preferences::load(db, user_id).await?;
+
+    let mut profile = audit::snapshot(&user);
+    if let Value::Object(fields) = &mut profile {
+        fields.insert(
+            "preferences".to_string(),
+            serde_json::to_value(&preferences).unwrap_or(Value::Null),
+        );
+        fields.insert(
+            "roles".to_string(),
+            serde_json::to_value(roles.iter().map(|role| &role.name).collect::<Vec<_>>())
+                .unwrap_or(Value::Null),
+        );
+    }
+
+    let documents = vec![
+        ("profile.json", profile),
+        ("login_history.json", to_json(&login_history, &[])),
+        ("sessions.json", to_json(&sessions, SESSION_SECRET_FIELDS)),
+        ("username_history.json", to_json(&username_history, &[])),
+        ("audit_log.json", to_json(&audit_entries, &[])),
+        ("intentions.json", to_json(&intentions, &[])),
+    ];
+
+    tokio::task::spawn_blocking(move || write_zip(documents))
+        .await
+        .map_err(|e| AppError::Internal(format!("Archive task failed: {}", e)))?
+}
+
+/// Contact requests sent from a user's address
+///
+/// Intentions store the address as typed, so it is compared trimmed and
+/// lowercased against the normalized user email.
+pub(super) fn intentions_of(normalized_email: &str) -> SimpleExpr {
+    Expr::cust_with_values("lower(trim(email)) = $1", [normalized_email])
+}
+
+/// Drop state and parameters from an audit row about someone other than the user
+///
+/// Their own actions on other accounts are exported, but not what those
+/// accounts looked like or what was submitted about them.
+fn strip_other_subjects(mut entry: audit_logs::Model, user_id: i32) -> audit_logs::Model {
+    let about_user =
+        matches!(entry.entity.as_str(), "user" | "users") && entry.entity_id == user_id.to_string();
+
+    if !about_user {
+        entry.before = None;
+        entry.after = None;
+        entry.changed_fields = None;
+        entry.request_params = None;
+        entry.query_params = None;
+        entry.metadata = None;
+    }
+
+    entry
+}
+
+/// Serialize rows as a JSON array, dropping the given fields from each row
+fn to_json<T: serde::Serialize>(rows: &[T], hidden_fields: &[&str]) -> Value {
+    let mut value = serde_json::to_value(rows).unwrap_or(Value::Null);
+
+    if let Value::Array(items) = &mut value {
+        for item in items.iter_mut() {
+            if let Value::Object(fields) = item {
+                for field in hidden_fields {
+                    fields.remove(*field);
+                }
+            }
+        }
+    }
+
+    value
+}
+
+/// Write JSON documents into an in-memory zip archive
+fn write_zip(documents: Vec<(&'static str, Value)>) -> Result<Vec<u8>> {
+    let archive_error = |e: &dyn std::fmt::Display| {
+        AppError::Internal(format!("Failed to write export archive: {}", e))
+    };
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+
+    for (name, document) in documents {
+        zip.start_file(name, options)
+            .map_err(|e| archive_error(&e))?;
+        let bytes = serde_json::to_vec_pretty(&document).map_err(|e| archive_error(&e))?;
+        zip.write_all(&bytes).map_err(|e| archive_error(&e))?;
+    }
+
+    let cursor = zip.finish().map_err(|e| archive_error(&e))?;
+
+    Ok(cursor.into_inner())
+}
+
+/// Build response, signing a short-lived download link for ready exports
+fn to_response(export: data_exports::Model, jwt_secret: &str) -> Result<DataExportResponse> {
+    let download_url = match (export.status.as_str(), export.expires_at) {
+        (EXPORT_STATUS_READY, Some(expires_at)) => {
+            let link_expires_at = (chrono::Utc::now()
+                + Duration::minutes(DOWNLOAD_LINK_TTL_MINUTES))
+            .min(expires_at.into());
+            let claims =
+                DownloadClaims::new(export.id, export.user_id, link_expires_at.timestamp());
+            let token = generate_download_token(&claims, jwt_secret)?;
+
+            Some(format!("/api/exports/download?token={}", token))
+        }
+        _ => None,
+    };
+
+    Ok(DataExportResponse {
+        id: export.id,
+        status: export.status,
+        created_at: export.created_at.into(),
+        completed_at: export.completed_at.map(Into::into),
+        expires_at: export.expires_at.map(Into::into),
+        download_url,
+    })
+}
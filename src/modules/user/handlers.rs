@@ -1,10 +1,39 @@
-use axum::{Extension, Json, extract::State};
+use axum::{
+    Extension, Json,
+    body::{Body, Bytes},
+    extract::{Multipart, Path, Query, State},
+    http::header,
+    response::IntoResponse,
+};
+use validator::Validate;
 
 use crate::{
-    common::{AppState, errors::Result, jwt::Claims, response::success},
-    modules::user::{
-        dto::{UserListItem, UserProfile},
-        service,
+    common::{
+        AppState, PaginatedResponse, PaginationParams, RequestContext,
+        errors::{AppError, Result},
+        jwt::Claims,
+        response::success,
+    },
+    modules::{
+        audit::service::{self as audit, AuditEntry},
+        user::{
+            deletion,
+            dto::{
+                AcceptInvitationRequest, AccountDeletionResponse, AddPhoneRequest,
+                AssignRolesRequest, AvatarResponse, AvatarUpload, ChangeEmailRequest,
+                ChangeUsernameRequest, ConfirmEmailChangeRequest, CreateInvitationRequest,
+                CreateUserRequest, DataExportResponse, DeleteAccountRequest, DownloadQuery,
+                EmailChangePending, ExportFormat, ImportReport, ImportUsersQuery,
+                InvitationResponse, LoginHistoryItem, PasswordResetResponse,
+                PhoneVerificationPending, RemovePhoneRequest, UpdatePreferencesRequest,
+                UpdateProfileRequest, UpdateSmsMfaRequest, UpdateUserRequest,
+                UpdateUserStatusRequest, UserExportQuery, UserImportUpload, UserListItem,
+                UserListQuery, UserPreferences, UserProfile, UserSearchQuery, UserSearchResult,
+                VerifyPhoneRequest,
+            },
+            export, import, invitation, login_history, phone, preferences, service, spreadsheet,
+            username,
+        },
     },
 };
 
@@ -26,17 +55,522 @@ pub async fn get_current_user(
     Extension(claims): Extension<Claims>,
 ) -> Result<Json<impl serde::Serialize>> {
     // Extract user ID from JWT claims
-    let user = service::get_user_by_id(&state.db, claims.sub).await?;
+    let user = service::get_user_by_id(&state.db, &claims, claims.sub).await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Update current user's nickname and avatar
+#[utoipa::path(
+    patch,
+    path = "/api/users/me",
+    request_body = UpdateProfileRequest,
+    responses(
+        (status = 200, description = "Profile updated", body = UserProfile),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "Nickname already exists"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn update_current_user(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let user = service::update_profile(&state.db, &ctx, &claims, payload).await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Change current user's username; the old one stays reserved for a while
+#[utoipa::path(
+    put,
+    path = "/api/users/me/username",
+    request_body = ChangeUsernameRequest,
+    responses(
+        (status = 200, description = "Username changed", body = UserProfile),
+        (status = 400, description = "Username unchanged"),
+        (status = 401, description = "Invalid password"),
+        (status = 403, description = "Change limit reached for the current period"),
+        (status = 409, description = "Username taken or reserved"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn change_username(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Json(payload): Json<ChangeUsernameRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let user =
+        username::change_username(&state.db, &state.permission_cache, &ctx, &claims, payload)
+            .await?;
 
     Ok(Json(success(user)))
 }
 
-/// Get list of all users (admin only)
+/// Get current user's preferences with defaults filled in
 #[utoipa::path(
     get,
-    path = "/api/users",
+    path = "/api/users/me/preferences",
+    responses(
+        (status = 200, description = "Preferences retrieved", body = UserPreferences),
+        (status = 401, description = "Unauthorized")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<impl serde::Serialize>> {
+    let preferences = preferences::load(&state.db, claims.sub).await?;
+
+    Ok(Json(success(preferences)))
+}
+
+/// Update current user's preferences; omitted fields are left unchanged
+#[utoipa::path(
+    patch,
+    path = "/api/users/me/preferences",
+    request_body = UpdatePreferencesRequest,
+    responses(
+        (status = 200, description = "Preferences updated", body = UserPreferences),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn update_preferences(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Json(payload): Json<UpdatePreferencesRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let preferences = preferences::update(&state.db, &ctx, &claims, payload).await?;
+
+    Ok(Json(success(preferences)))
+}
+
+/// Schedule deletion of the current user's account after the grace period
+#[utoipa::path(
+    delete,
+    path = "/api/users/me",
+    request_body = DeleteAccountRequest,
+    responses(
+        (status = 200, description = "Deletion scheduled", body = AccountDeletionResponse),
+        (status = 401, description = "Invalid password"),
+        (status = 409, description = "Deletion already scheduled"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn delete_current_user(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Json(payload): Json<DeleteAccountRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let deletion = deletion::schedule_own_deletion(&state.db, &ctx, &claims, payload).await?;
+
+    Ok(Json(success(deletion)))
+}
+
+/// Cancel the current user's scheduled account deletion
+#[utoipa::path(
+    post,
+    path = "/api/users/me/deletion/cancel",
+    responses(
+        (status = 200, description = "Deletion cancelled", body = UserProfile),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "No deletion scheduled")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn cancel_current_user_deletion(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+) -> Result<Json<impl serde::Serialize>> {
+    let user = deletion::cancel_own_deletion(&state.db, &ctx, &claims).await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Request an archive of all personal data held about the current user
+#[utoipa::path(
+    post,
+    path = "/api/users/me/exports",
+    responses(
+        (status = 200, description = "Export queued", body = DataExportResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "An export is already in progress")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn request_data_export(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+) -> Result<Json<impl serde::Serialize>> {
+    let export = export::request_export(
+        &state.db,
+        state.storage.clone(),
+        &ctx,
+        &claims,
+        &state.jwt_secret,
+    )
+    .await?;
+
+    Ok(Json(success(export)))
+}
+
+/// Get status of a data export and a fresh download link once it is ready
+#[utoipa::path(
+    get,
+    path = "/api/users/me/exports/{id}",
+    params(
+        ("id" = i32, Path, description = "Export identifier")
+    ),
+    responses(
+        (status = 200, description = "Export status", body = DataExportResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Export not found")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_data_export(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(export_id): Path<i32>,
+) -> Result<Json<impl serde::Serialize>> {
+    let export = export::get_export(&state.db, &claims, export_id, &state.jwt_secret).await?;
+
+    Ok(Json(success(export)))
+}
+
+/// Download a data export archive through a signed link
+#[utoipa::path(
+    get,
+    path = "/api/exports/download",
+    params(DownloadQuery),
+    responses(
+        (status = 200, description = "Zip archive", content_type = "application/zip"),
+        (status = 401, description = "Invalid or expired download link"),
+        (status = 404, description = "Export not found")
+    ),
+    tag = "Users"
+)]
+pub async fn download_data_export(
+    State(state): State<AppState>,
+    Query(query): Query<DownloadQuery>,
+) -> Result<impl IntoResponse> {
+    let (file_name, bytes) = export::download_export(
+        &state.db,
+        state.storage.as_ref(),
+        &query.token,
+        &state.jwt_secret,
+    )
+    .await?;
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file_name),
+            ),
+        ],
+        bytes,
+    ))
+}
+
+/// Upload a new avatar for the current user
+#[utoipa::path(
+    post,
+    path = "/api/users/me/avatar",
+    request_body(content = AvatarUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Avatar updated", body = AvatarResponse),
+        (status = 400, description = "Missing, oversized or unsupported image"),
+        (status = 401, description = "Unauthorized"),
+        (status = 413, description = "Request body too large")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn upload_avatar(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    multipart: Multipart,
+) -> Result<Json<impl serde::Serialize>> {
+    let file = read_file_field(multipart).await?;
+
+    let avatar = service::upload_avatar(
+        &state.db,
+        state.storage.as_ref(),
+        &ctx,
+        &claims,
+        file.to_vec(),
+    )
+    .await?;
+
+    Ok(Json(success(avatar)))
+}
+
+/// Request a change of the current user's email address
+#[utoipa::path(
+    post,
+    path = "/api/users/me/email",
+    request_body = ChangeEmailRequest,
+    responses(
+        (status = 200, description = "Verification sent to the new address", body = EmailChangePending),
+        (status = 400, description = "Email unchanged"),
+        (status = 401, description = "Invalid password"),
+        (status = 409, description = "Email already exists"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn request_email_change(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<ChangeEmailRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let pending =
+        service::request_email_change(&state.db, state.mailer.as_ref(), &claims, payload).await?;
+
+    Ok(Json(success(pending)))
+}
+
+/// Confirm a pending email change with the emailed token
+#[utoipa::path(
+    post,
+    path = "/api/users/me/email/confirm",
+    request_body = ConfirmEmailChangeRequest,
+    responses(
+        (status = 200, description = "Email changed", body = UserProfile),
+        (status = 400, description = "Invalid or expired token"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "Email already exists"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn confirm_email_change(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Json(payload): Json<ConfirmEmailChangeRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let user = service::confirm_email_change(&state.db, &ctx, &claims, payload).await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Start adding or replacing current user's phone number; a code is sent by SMS
+#[utoipa::path(
+    put,
+    path = "/api/users/me/phone",
+    request_body = AddPhoneRequest,
+    responses(
+        (status = 200, description = "Verification code sent", body = PhoneVerificationPending),
+        (status = 400, description = "Number already verified or code requested too recently"),
+        (status = 401, description = "Invalid password"),
+        (status = 409, description = "Phone number already in use"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn add_phone(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<AddPhoneRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let pending =
+        phone::request_verification(&state.db, state.sms.as_ref(), &claims, payload).await?;
+
+    Ok(Json(success(pending)))
+}
+
+/// Confirm current user's pending phone number with the code sent by SMS
+#[utoipa::path(
+    post,
+    path = "/api/users/me/phone/verify",
+    request_body = VerifyPhoneRequest,
+    responses(
+        (status = 200, description = "Phone number verified", body = UserProfile),
+        (status = 400, description = "Invalid or expired code"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "Phone number already in use"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn verify_phone(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Json(payload): Json<VerifyPhoneRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let user = phone::confirm_verification(&state.db, &ctx, &claims, payload).await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Remove current user's phone number and turn off SMS login codes
+#[utoipa::path(
+    delete,
+    path = "/api/users/me/phone",
+    request_body = RemovePhoneRequest,
+    responses(
+        (status = 200, description = "Phone number removed", body = UserProfile),
+        (status = 400, description = "No phone number on the account"),
+        (status = 401, description = "Invalid password"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn remove_phone(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Json(payload): Json<RemovePhoneRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let user = phone::remove_phone(&state.db, &ctx, &claims, payload).await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Turn SMS login codes on or off for current user
+#[utoipa::path(
+    put,
+    path = "/api/users/me/mfa/sms",
+    request_body = UpdateSmsMfaRequest,
+    responses(
+        (status = 200, description = "SMS login codes updated", body = UserProfile),
+        (status = 400, description = "No verified phone number"),
+        (status = 401, description = "Invalid password"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn update_sms_mfa(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Json(payload): Json<UpdateSmsMfaRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let user = phone::update_sms_mfa(&state.db, &ctx, &claims, payload).await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Get current user's login history, newest first
+#[utoipa::path(
+    get,
+    path = "/api/users/me/login-history",
+    params(PaginationParams),
     responses(
-        (status = 200, description = "List of users", body = Vec<UserListItem>),
+        (status = 200, description = "Paginated login history", body = Vec<LoginHistoryItem>),
+        (status = 400, description = "Invalid pagination parameters"),
         (status = 401, description = "Unauthorized")
     ),
     tag = "Users",
@@ -44,8 +578,730 @@ pub async fn get_current_user(
         ("bearer_auth" = [])
     )
 )]
-pub async fn list_users(State(state): State<AppState>) -> Result<Json<impl serde::Serialize>> {
-    let users = service::list_users(&state.db).await?;
+pub async fn get_login_history(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<PaginatedResponse<LoginHistoryItem>> {
+    // Validate query parameters
+    pagination.validate().map_err(AppError::BadRequest)?;
+
+    let (logs, total) = login_history::list(&state.db, claims.sub, &pagination).await?;
+
+    Ok(PaginatedResponse::new(
+        logs,
+        pagination.page,
+        pagination.page_size,
+        total,
+    ))
+}
+
+/// Get paginated, filterable list of users (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/users",
+    params(PaginationParams, UserListQuery),
+    responses(
+        (status = 200, description = "Paginated list of users", body = Vec<UserListItem>),
+        (status = 400, description = "Invalid pagination parameters"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn list_users(
+    State(state): State<AppState>,
+    Query(pagination): Query<PaginationParams>,
+    Query(query): Query<UserListQuery>,
+) -> Result<PaginatedResponse<UserListItem>> {
+    // Validate query parameters
+    pagination.validate().map_err(AppError::BadRequest)?;
+    query
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let (users, total) = service::list_users(&state.db, &pagination, &query).await?;
+
+    Ok(PaginatedResponse::new(
+        users,
+        pagination.page,
+        pagination.page_size,
+        total,
+    ))
+}
+
+/// Fuzzy search users by username, email or nickname (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/users/search",
+    params(UserSearchQuery),
+    responses(
+        (status = 200, description = "Users ranked by similarity", body = Vec<UserSearchResult>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn search_users(
+    State(state): State<AppState>,
+    Query(query): Query<UserSearchQuery>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate query parameters
+    query
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let results = service::search_users(&state.db, &query).await?;
+
+    Ok(Json(success(results)))
+}
+
+/// Export users matching the list filters as CSV or XLSX (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/users/export",
+    params(UserExportQuery, UserListQuery),
+    responses(
+        (status = 200, description = "Spreadsheet of users", content(
+            ("text/csv"),
+            ("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet")
+        )),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn export_users(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Query(export): Query<UserExportQuery>,
+    Query(query): Query<UserListQuery>,
+) -> Result<impl IntoResponse> {
+    // Validate query parameters
+    query
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    audit::record(
+        &state.db,
+        &ctx,
+        &claims,
+        AuditEntry::new("user.export", "user", "*").after(serde_json::json!({
+            "format": export.format,
+            "filters": query,
+        })),
+    )
+    .await?
+    .attach();
+
+    let date = chrono::Utc::now().format("%Y%m%d");
+    let (content_type, file_name, body) = match export.format {
+        ExportFormat::Csv => (
+            "text/csv; charset=utf-8",
+            format!("users-{}.csv", date),
+            spreadsheet::csv_stream(state.db.clone(), query),
+        ),
+        ExportFormat::Xlsx => (
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+            format!("users-{}.xlsx", date),
+            spreadsheet::xlsx_stream(state.db.clone(), query).await?,
+        ),
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file_name),
+            ),
+        ],
+        Body::from_stream(body),
+    ))
+}
+
+/// Get any user's profile (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}",
+    params(
+        ("id" = i32, Path, description = "User identifier")
+    ),
+    responses(
+        (status = 200, description = "User profile retrieved successfully", body = UserProfile),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_user(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(user_id): Path<i32>,
+) -> Result<Json<impl serde::Serialize>> {
+    let user = service::get_user_by_id(&state.db, &claims, user_id).await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Get a user's login history, newest first (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/users/{id}/login-history",
+    params(
+        ("id" = i32, Path, description = "User identifier"),
+        PaginationParams
+    ),
+    responses(
+        (status = 200, description = "Paginated login history", body = Vec<LoginHistoryItem>),
+        (status = 400, description = "Invalid pagination parameters"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_user_login_history(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(user_id): Path<i32>,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<PaginatedResponse<LoginHistoryItem>> {
+    // Validate query parameters
+    pagination.validate().map_err(AppError::BadRequest)?;
+
+    let (logs, total) =
+        login_history::list_for_user(&state.db, &claims, user_id, &pagination).await?;
+
+    Ok(PaginatedResponse::new(
+        logs,
+        pagination.page,
+        pagination.page_size,
+        total,
+    ))
+}
+
+/// Get a user's profile by username, including recently changed ones (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/users/by-username/{username}",
+    params(
+        ("username" = String, Path, description = "Current or still-reserved previous username")
+    ),
+    responses(
+        (status = 200, description = "User profile retrieved successfully", body = UserProfile),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn get_user_by_username(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(name): Path<String>,
+) -> Result<Json<impl serde::Serialize>> {
+    let user = username::get_by_username(&state.db, &claims, &name).await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Create a user (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/users",
+    request_body = CreateUserRequest,
+    responses(
+        (status = 200, description = "User created", body = UserProfile),
+        (status = 400, description = "Unknown role"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 409, description = "Username, email or nickname already exists"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn create_user(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Json(payload): Json<CreateUserRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let user = service::admin_create_user(&state.db, &ctx, &claims, payload).await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Create users in bulk from a CSV file (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/users/import",
+    params(ImportUsersQuery),
+    request_body(content = UserImportUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Import report with created users or invitations and per-row errors", body = ImportReport),
+        (status = 400, description = "Missing or malformed CSV"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn import_users(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Query(query): Query<ImportUsersQuery>,
+    multipart: Multipart,
+) -> Result<Json<impl serde::Serialize>> {
+    let file = read_file_field(multipart).await?;
+
+    let report = import::import_users(
+        &state.db,
+        state.mailer.as_ref(),
+        &ctx,
+        &claims,
+        &file,
+        query.dry_run,
+        query.delivery,
+    )
+    .await?;
+
+    Ok(Json(success(report)))
+}
+
+/// List invitations awaiting acceptance (admin only)
+#[utoipa::path(
+    get,
+    path = "/api/users/invitations",
+    params(PaginationParams),
+    responses(
+        (status = 200, description = "Paginated list of pending invitations", body = Vec<InvitationResponse>),
+        (status = 400, description = "Invalid pagination parameters"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn list_invitations(
+    State(state): State<AppState>,
+    Query(pagination): Query<PaginationParams>,
+) -> Result<PaginatedResponse<InvitationResponse>> {
+    // Validate query parameters
+    pagination.validate().map_err(AppError::BadRequest)?;
+
+    let (invitations, total) = invitation::list_pending(&state.db, &pagination).await?;
+
+    Ok(PaginatedResponse::new(
+        invitations,
+        pagination.page,
+        pagination.page_size,
+        total,
+    ))
+}
+
+/// Invite someone by email with a preselected role (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/users/invitations",
+    request_body = CreateInvitationRequest,
+    responses(
+        (status = 200, description = "Invitation sent", body = InvitationResponse),
+        (status = 400, description = "Unknown or disabled role"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 409, description = "Email already registered or already invited"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn create_invitation(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Json(payload): Json<CreateInvitationRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let invitation =
+        invitation::create_invitation(&state.db, state.mailer.as_ref(), &ctx, &claims, payload)
+            .await?;
+
+    Ok(Json(success(invitation)))
+}
+
+/// Send a pending invitation again with a fresh token (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/users/invitations/{id}/resend",
+    params(
+        ("id" = i32, Path, description = "Invitation identifier")
+    ),
+    responses(
+        (status = 200, description = "Invitation resent", body = InvitationResponse),
+        (status = 400, description = "Invited role no longer available"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Invitation not found")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn resend_invitation(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Path(invitation_id): Path<i32>,
+) -> Result<Json<impl serde::Serialize>> {
+    let invitation = invitation::resend_invitation(
+        &state.db,
+        state.mailer.as_ref(),
+        &ctx,
+        &claims,
+        invitation_id,
+    )
+    .await?;
+
+    Ok(Json(success(invitation)))
+}
+
+/// Withdraw a pending invitation (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/users/invitations/{id}",
+    params(
+        ("id" = i32, Path, description = "Invitation identifier")
+    ),
+    responses(
+        (status = 200, description = "Invitation revoked"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Invitation not found")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn revoke_invitation(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Path(invitation_id): Path<i32>,
+) -> Result<Json<impl serde::Serialize>> {
+    invitation::revoke_invitation(&state.db, &ctx, &claims, invitation_id).await?;
+
+    Ok(Json(success(serde_json::json!({
+        "message": "Invitation revoked."
+    }))))
+}
+
+/// Accept an invitation by choosing a username and password
+#[utoipa::path(
+    post,
+    path = "/api/invitations/accept",
+    request_body = AcceptInvitationRequest,
+    responses(
+        (status = 200, description = "Account created", body = UserProfile),
+        (status = 400, description = "Invalid or expired invitation"),
+        (status = 409, description = "Username or nickname already exists"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users"
+)]
+pub async fn accept_invitation(
+    State(state): State<AppState>,
+    Json(payload): Json<AcceptInvitationRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let user = invitation::accept_invitation(&state.db, payload).await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Update a user's attributes (admin only)
+#[utoipa::path(
+    patch,
+    path = "/api/users/{id}",
+    params(
+        ("id" = i32, Path, description = "User identifier")
+    ),
+    request_body = UpdateUserRequest,
+    responses(
+        (status = 200, description = "User updated", body = UserProfile),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "Email or nickname already exists"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn update_user(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Path(user_id): Path<i32>,
+    Json(payload): Json<UpdateUserRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let user = service::update_user(&state.db, &ctx, &claims, user_id, payload).await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Schedule deletion of a user after the grace period (admin only)
+#[utoipa::path(
+    delete,
+    path = "/api/users/{id}",
+    params(
+        ("id" = i32, Path, description = "User identifier")
+    ),
+    responses(
+        (status = 200, description = "Deletion scheduled", body = AccountDeletionResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "Deletion already scheduled or account already deleted")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn delete_user(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Path(user_id): Path<i32>,
+) -> Result<Json<impl serde::Serialize>> {
+    let deletion = deletion::schedule_deletion(&state.db, &ctx, &claims, user_id).await?;
+
+    Ok(Json(success(deletion)))
+}
+
+/// Cancel a user's scheduled deletion (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/users/{id}/deletion/cancel",
+    params(
+        ("id" = i32, Path, description = "User identifier")
+    ),
+    responses(
+        (status = 200, description = "Deletion cancelled", body = UserProfile),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "No deletion scheduled")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn cancel_user_deletion(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Path(user_id): Path<i32>,
+) -> Result<Json<impl serde::Serialize>> {
+    let user = deletion::cancel_deletion(&state.db, &ctx, &claims, user_id).await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Replace a user's roles (admin only)
+#[utoipa::path(
+    put,
+    path = "/api/users/{id}/roles",
+    params(
+        ("id" = i32, Path, description = "User identifier")
+    ),
+    request_body = AssignRolesRequest,
+    responses(
+        (status = 200, description = "Roles assigned", body = UserProfile),
+        (status = 400, description = "Unknown role"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn assign_roles(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Path(user_id): Path<i32>,
+    Json(payload): Json<AssignRolesRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let user = service::assign_roles(
+        &state.db,
+        &state.permission_cache,
+        &ctx,
+        &claims,
+        user_id,
+        payload,
+    )
+    .await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Change a user's lifecycle status (admin only)
+#[utoipa::path(
+    patch,
+    path = "/api/users/{id}/status",
+    params(
+        ("id" = i32, Path, description = "User identifier")
+    ),
+    request_body = UpdateUserStatusRequest,
+    responses(
+        (status = 200, description = "User status updated", body = UserProfile),
+        (status = 400, description = "Deletion must use the delete endpoint"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "Transition not allowed"),
+        (status = 422, description = "Validation error")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn update_user_status(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Path(user_id): Path<i32>,
+    Json(payload): Json<UpdateUserStatusRequest>,
+) -> Result<Json<impl serde::Serialize>> {
+    // Validate request payload
+    payload
+        .validate()
+        .map_err(|e| AppError::ValidationError(e.to_string()))?;
+
+    let user = service::update_status(
+        &state.db,
+        &state.permission_cache,
+        &ctx,
+        &claims,
+        user_id,
+        payload,
+    )
+    .await?;
+
+    Ok(Json(success(user)))
+}
+
+/// Reset a user's password and force a change at next login (admin only)
+#[utoipa::path(
+    post,
+    path = "/api/users/{id}/password-reset",
+    params(
+        ("id" = i32, Path, description = "User identifier")
+    ),
+    responses(
+        (status = 200, description = "Temporary password issued", body = PasswordResetResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found")
+    ),
+    tag = "Users",
+    security(
+        ("bearer_auth" = [])
+    )
+)]
+pub async fn reset_user_password(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    ctx: RequestContext,
+    Path(user_id): Path<i32>,
+) -> Result<Json<impl serde::Serialize>> {
+    let credentials =
+        service::reset_password(&state.db, &state.permission_cache, &ctx, &claims, user_id).await?;
+
+    Ok(Json(success(credentials)))
+}
+
+/// Read the `file` field of a multipart upload
+async fn read_file_field(mut multipart: Multipart) -> Result<Bytes> {
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::BadRequest(e.to_string()))?
+    {
+        if field.name() == Some("file") {
+            return field
+                .bytes()
+                .await
+                .map_err(|e| AppError::BadRequest(e.to_string()));
+        }
+    }
 
-    Ok(Json(success(users)))
+    Err(AppError::BadRequest("Missing 'file' field".to_string()))
 }
@@ -0,0 +1,535 @@
+use sea_orm::*;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    common::{
+        RequestContext,
+        errors::{AppError, Result},
+        jwt::Claims,
+        mailer::Mailer,
+        password::generate_temporary_password,
+        validator::{normalize_email, username_skeleton, validate_email},
+    },
+    entity::{invitations, roles, username_history, users},
+    modules::{
+        audit::{
+            change::{EntityChange, RecordedChange},
+            service::{self as audit, AuditEntry},
+        },
+        role::service::{self as role_service, ROLE_STATUS_ACTIVE},
+        system::service::{self as system_service, DEFAULT_ROLE_SETTING},
+        user::{
+            dto::{
+                CredentialDelivery, ImportReport, ImportRowError, ImportedInvitation, ImportedUser,
+            },
+            invitation,
+            service::{NewUser, create_user},
+            username::UsernamePolicy,
+        },
+    },
+};
+
+/// Largest number of data rows accepted in one file
+pub const MAX_IMPORT_ROWS: usize = 5000;
+
+/// Users created per transaction
+const IMPORT_BATCH_SIZE: usize = 100;
+
+/// One CSV data row
+#[derive(Debug, Deserialize)]
+struct ImportRow {
+    #[serde(default)]
+    username: String,
+    email: String,
+    #[serde(default)]
+    nickname: String,
+    #[serde(default)]
+    role: String,
+}
+
+/// Normalize the email so duplicates are detected the way the database sees them
+///
+/// Unparseable addresses are left as-is for validation to report.
+fn normalize_row(mut row: ImportRow) -> ImportRow {
+    if let Ok(email) = normalize_email(&row.email) {
+        row.email = email;
+    }
+    row
+}
+
+/// Row that passed validation; username and nickname are empty for invitations
+struct ValidRow {
+    line: u64,
+    username: String,
+    email: String,
+    nickname: String,
+    role: roles::Model,
+}
+
+/// Rows created by one batch
+#[derive(Default)]
+struct BatchOutcome {
+    users: Vec<(u64, users::Model, String)>,
+    invitations: Vec<(u64, invitations::Model, roles::Model, String)>,
+    errors: Vec<ImportRowError>,
+    /// Audit references of the rows whose savepoints committed
+    changes: Vec<RecordedChange>,
+}
+
+/// Validate a CSV of users and, unless `dry_run`, create the valid rows in batches
+///
+/// Invalid rows are skipped and reported so one typo does not block the whole
+/// file; rows that fail while being created are reported the same way.
+pub async fn import_users(
+    db: &DatabaseConnection,
+    mailer: &dyn Mailer,
+    ctx: &RequestContext,
+    claims: &Claims,
+    csv: &[u8],
+    dry_run: bool,
+    delivery: CredentialDelivery,
+) -> Result<ImportReport> {
+    let rows = parse(csv, delivery)?;
+    let total_rows = rows.len();
+
+    let (valid, mut errors) = validate(db, claims, rows, delivery).await?;
+    let valid_rows = valid.len();
+
+    let (created, invited) = if dry_run {
+        (Vec::new(), Vec::new())
+    } else {
+        create_batches(db, mailer, ctx, claims, valid, delivery, &mut errors).await
+    };
+    errors.sort_by_key(|error| error.line);
+
+    Ok(ImportReport {
+        dry_run,
+        total_rows,
+        valid_rows,
+        created,
+        invited,
+        errors,
+    })
+}
+
+/// Parse CSV into rows keyed by line number, or per-row deserialization errors
+fn parse(
+    csv: &[u8],
+    delivery: CredentialDelivery,
+) -> Result<Vec<(u64, std::result::Result<ImportRow, String>)>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(csv);
+
+    let headers = reader
+        .headers()
+        .map_err(|e| AppError::BadRequest(format!("Invalid CSV header: {}", e)))?
+        .clone();
+    // Invitees choose their own username and nickname
+    let required: &[&str] = match delivery {
+        CredentialDelivery::Invitation => &["email"],
+        CredentialDelivery::TemporaryPassword => &["username", "email", "nickname"],
+    };
+    for required in required {
+        if !headers.iter().any(|header| header == *required) {
+            return Err(AppError::BadRequest(format!(
+                "CSV header is missing the '{}' column",
+                required
+            )));
+        }
+    }
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        if rows.len() == MAX_IMPORT_ROWS {
+            return Err(AppError::BadRequest(format!(
+                "CSV must not contain more than {} rows",
+                MAX_IMPORT_ROWS
+            )));
+        }
+
+        let row = match record {
+            Ok(record) => (
+                record.position().map_or(0, |position| position.line()),
+                record
+                    .deserialize(Some(&headers))
+                    .map(normalize_row)
+                    .map_err(|e| format!("Unreadable row: {}", e)),
+            ),
+            Err(e) => (
+                e.position().map_or(0, |position| position.line()),
+                Err(format!("Unreadable row: {}", e)),
+            ),
+        };
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Check rows against field rules, each other, existing users and the caller's permissions
+async fn validate(
+    db: &DatabaseConnection,
+    claims: &Claims,
+    rows: Vec<(u64, std::result::Result<ImportRow, String>)>,
+    delivery: CredentialDelivery,
+) -> Result<(Vec<ValidRow>, Vec<ImportRowError>)> {
+    let roles_by_name: HashMap<String, roles::Model> = roles::Entity::find()
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|role| (role.name.clone(), role))
+        .collect();
+    let default_role_id: i32 = system_service::require_setting(db, DEFAULT_ROLE_SETTING).await?;
+
+    let parsed: Vec<&ImportRow> = rows
+        .iter()
+        .filter_map(|(_, row)| row.as_ref().ok())
+        .collect();
+    let taken = TakenValues::load(db, &parsed).await?;
+    let username_policy = UsernamePolicy::load(db).await?;
+    let invite = delivery == CredentialDelivery::Invitation;
+
+    let mut seen_usernames = HashSet::new();
+    let mut seen_emails = HashSet::new();
+    let mut seen_nicknames = HashSet::new();
+    // Why each role cannot be granted, or `None` once it has been checked and can
+    let mut role_problems: HashMap<i32, Option<String>> = HashMap::new();
+
+    let mut valid = Vec::new();
+    let mut invalid = Vec::new();
+
+    for (line, row) in rows {
+        let mut row = match row {
+            Ok(row) => row,
+            Err(e) => {
+                invalid.push(ImportRowError {
+                    line,
+                    errors: vec![e],
+                });
+                continue;
+            }
+        };
+
+        let mut errors = Vec::new();
+
+        if !invite {
+            match username_policy.check(&row.username) {
+                Err(e) => errors.push(message(e)),
+                Ok((username, skeleton)) => {
+                    if taken.usernames.contains(&username) {
+                        errors.push("Username already exists".to_string());
+                    } else if taken.username_skeletons.contains(&skeleton) {
+                        errors.push("Username is too similar to an existing one".to_string());
+                    } else if !seen_usernames.insert(skeleton) {
+                        errors.push("Duplicate or lookalike username in file".to_string());
+                    }
+                    row.username = username;
+                }
+            }
+        }
+
+        if let Err(e) = validate_email(&row.email) {
+            errors.push(message(e));
+        } else if taken.emails.contains(&row.email) {
+            errors.push("Email already exists".to_string());
+        } else if taken.invited_emails.contains(&row.email) {
+            errors.push("An invitation is already pending for this email".to_string());
+        } else if !seen_emails.insert(row.email.clone()) {
+            errors.push("Duplicate email in file".to_string());
+        }
+
+        if !invite {
+            let nickname_length = row.nickname.chars().count();
+            if !(2..=100).contains(&nickname_length) {
+                errors.push("Nickname must be 2-100 characters long".to_string());
+            } else if taken.nicknames.contains(&row.nickname) {
+                errors.push("Nickname already exists".to_string());
+            } else if !seen_nicknames.insert(row.nickname.clone()) {
+                errors.push("Duplicate nickname in file".to_string());
+            }
+        }
+
+        let role = if row.role.is_empty() {
+            roles_by_name
+                .values()
+                .find(|role| role.id == default_role_id)
+        } else {
+            roles_by_name.get(&row.role)
+        };
+        match role {
+            None => errors.push(format!("Unknown role '{}'", row.role)),
+            Some(role) => {
+                let problem = match role_problems.get(&role.id) {
+                    Some(problem) => problem.clone(),
+                    None => {
+                        let problem = role_problem(db, claims, role).await?;
+                        role_problems.insert(role.id, problem.clone());
+                        problem
+                    }
+                };
+                errors.extend(problem);
+            }
+        }
+
+        match role {
+            Some(role) if errors.is_empty() => valid.push(ValidRow {
+                line,
+                username: row.username,
+                email: row.email,
+                nickname: row.nickname,
+                role: role.clone(),
+            }),
+            _ => invalid.push(ImportRowError { line, errors }),
+        }
+    }
+
+    Ok((valid, invalid))
+}
+
+/// Why a role cannot be given to imported users, if anything
+async fn role_problem(
+    db: &DatabaseConnection,
+    claims: &Claims,
+    role: &roles::Model,
+) -> Result<Option<String>> {
+    if role.status != ROLE_STATUS_ACTIVE {
+        return Ok(Some(format!("Role '{}' is disabled", role.name)));
+    }
+
+    match role_service::ensure_grantable(db, claims, &[role.id]).await {
+        Ok(()) => Ok(None),
+        Err(AppError::Forbidden(problem)) => Ok(Some(problem)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Create valid rows one transaction per batch and deliver their credentials
+///
+/// Each row runs in a savepoint so a failing row is reported and skipped
+/// without losing the rest of its batch; a batch that cannot commit reports
+/// all of its rows.
+async fn create_batches(
+    db: &DatabaseConnection,
+    mailer: &dyn Mailer,
+    ctx: &RequestContext,
+    claims: &Claims,
+    rows: Vec<ValidRow>,
+    delivery: CredentialDelivery,
+    errors: &mut Vec<ImportRowError>,
+) -> (Vec<ImportedUser>, Vec<ImportedInvitation>) {
+    let mut created = Vec::new();
+    let mut invited = Vec::new();
+    let mut rows = rows.into_iter().peekable();
+
+    while rows.peek().is_some() {
+        let batch: Vec<ValidRow> = rows.by_ref().take(IMPORT_BATCH_SIZE).collect();
+        let lines: Vec<u64> = batch.iter().map(|row| row.line).collect();
+
+        let outcome = match create_batch(db, ctx, claims, batch, delivery).await {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                tracing::error!("Failed to import batch of {} users: {}", lines.len(), e);
+                let reason = format!("Not created: {}", message(e));
+                errors.extend(lines.into_iter().map(|line| ImportRowError {
+                    line,
+                    errors: vec![reason.clone()],
+                }));
+                continue;
+            }
+        };
+        errors.extend(outcome.errors);
+
+        for (line, user, temporary_password) in outcome.users {
+            created.push(ImportedUser {
+                line,
+                id: user.id,
+                username: user.username,
+                temporary_password,
+            });
+        }
+
+        for (line, invitation, role, token) in outcome.invitations {
+            // The invitation is stored; an admin can resend it if delivery failed
+            if let Err(e) = invitation::send_invitation(mailer, &invitation, &role, &token).await {
+                tracing::warn!("Failed to send invitation {}: {}", invitation.id, e);
+            }
+
+            invited.push(ImportedInvitation {
+                line,
+                id: invitation.id,
+                email: invitation.email,
+            });
+        }
+    }
+
+    (created, invited)
+}
+
+/// Create one batch of rows in a single transaction
+async fn create_batch(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    batch: Vec<ValidRow>,
+    delivery: CredentialDelivery,
+) -> Result<BatchOutcome> {
+    let mut outcome = BatchOutcome::default();
+
+    let txn = db.begin().await?;
+
+    for row in batch {
+        let line = row.line;
+        let savepoint = txn.begin().await?;
+
+        let result = match delivery {
+            CredentialDelivery::TemporaryPassword => create_row_user(&savepoint, ctx, claims, row)
+                .await
+                .map(|(user, password, recorded)| {
+                    outcome.users.push((line, user, password));
+                    outcome.changes.push(recorded);
+                }),
+            CredentialDelivery::Invitation => {
+                invitation::insert_invitation(&savepoint, ctx, claims, row.email, &row.role)
+                    .await
+                    .map(|(invitation, token, recorded)| {
+                        outcome
+                            .invitations
+                            .push((line, invitation, row.role, token));
+                        outcome.changes.push(recorded);
+                    })
+            }
+        };
+
+        match result {
+            Ok(()) => savepoint.commit().await?,
+            Err(e) => {
+                savepoint.rollback().await?;
+                outcome.errors.push(ImportRowError {
+                    line,
+                    errors: vec![message(e)],
+                });
+            }
+        }
+    }
+
+    txn.commit().await?;
+    for recorded in outcome.changes.drain(..) {
+        recorded.attach();
+    }
+
+    Ok(outcome)
+}
+
+/// Create the user for one row with a temporary password that must be changed
+///
+/// Attach the returned audit reference once the transaction commits.
+async fn create_row_user<C: ConnectionTrait>(
+    db: &C,
+    ctx: &RequestContext,
+    claims: &Claims,
+    row: ValidRow,
+) -> Result<(users::Model, String, RecordedChange)> {
+    let temporary_password = generate_temporary_password();
+    let user = create_user(
+        db,
+        NewUser {
+            username: row.username,
+            email: row.email,
+            password: temporary_password.clone(),
+            nickname: row.nickname,
+            role_ids: vec![row.role.id],
+            password_change_required: true,
+        },
+    )
+    .await?;
+
+    let recorded = audit::record(
+        db,
+        ctx,
+        claims,
+        AuditEntry::new("user.import", "user", user.id).change(EntityChange::created(&user)),
+    )
+    .await?;
+
+    Ok((user, temporary_password, recorded))
+}
+
+/// Extract the human readable part of a validation error
+fn message(error: AppError) -> String {
+    match error {
+        AppError::ValidationError(message) => message,
+        other => other.to_string(),
+    }
+}
+
+/// Usernames, emails and nicknames from the file that already belong to users or pending invitations
+struct TakenValues {
+    usernames: HashSet<String>,
+    username_skeletons: HashSet<String>,
+    emails: HashSet<String>,
+    invited_emails: HashSet<String>,
+    nicknames: HashSet<String>,
+}
+
+impl TakenValues {
+    async fn load(db: &DatabaseConnection, rows: &[&ImportRow]) -> Result<Self> {
+        let skeletons: Vec<String> = rows
+            .iter()
+            .map(|row| username_skeleton(&row.username))
+            .collect();
+
+        let existing = users::Entity::find()
+            .filter(
+                Condition::any()
+                    .add(
+                        users::Column::Username.is_in(rows.iter().map(|row| row.username.as_str())),
+                    )
+                    .add(
+                        users::Column::UsernameSkeleton.is_in(skeletons.iter().map(String::as_str)),
+                    )
+                    .add(users::Column::Email.is_in(rows.iter().map(|row| row.email.as_str())))
+                    .add(
+                        users::Column::Nickname.is_in(rows.iter().map(|row| row.nickname.as_str())),
+                    ),
+            )
+            .all(db)
+            .await?;
+
+        // Previous usernames still reserved for their former owners
+        let reserved = username_history::Entity::find()
+            .filter(
+                username_history::Column::Username
+                    .is_in(rows.iter().map(|row| row.username.as_str())),
+            )
+            .filter(username_history::Column::ReservedUntil.gt(chrono::Utc::now()))
+            .all(db)
+            .await?;
+
+        let invited = invitations::Entity::find()
+            .filter(invitations::Column::Email.is_in(rows.iter().map(|row| row.email.as_str())))
+            .filter(invitations::Column::AcceptedAt.is_null())
+            .filter(invitations::Column::RevokedAt.is_null())
+            .all(db)
+            .await?;
+
+        Ok(Self {
+            usernames: existing
+                .iter()
+                .map(|user| user.username.clone())
+                .chain(reserved.into_iter().map(|entry| entry.username))
+                .collect(),
+            username_skeletons: existing
+                .iter()
+                .filter_map(|user| user.username_skeleton.clone())
+                .collect(),
+            emails: existing.iter().map(|user| user.email.clone()).collect(),
+            invited_emails: invited
+                .into_iter()
+                .map(|invitation| invitation.email)
+                .collect(),
+            nicknames: existing.iter().map(|user| user.nickname.clone()).collect(),
+        })
+    }
+}
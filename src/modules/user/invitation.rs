@@ -0,0 +1,363 @@
+use chrono::Duration;
+use sea_orm::{sea_query::Expr, *};
+
+use crate::{
+    common::{
+        RequestContext,
+        errors::{AppError, Result},
+        jwt::Claims,
+        mailer::{Email, Mailer},
+        pagination::PaginationParams,
+        token::{generate_token, hash_token},
+        validator::normalize_email,
+    },
+    entity::{invitations, roles},
+    modules::{
+        audit::{
+            change::{EntityChange, RecordedChange},
+            service::{self as audit, AuditEntry, RiskLevel},
+        },
+        role::{
+            dto::RoleSummary,
+            service::{self as role_service, ROLE_STATUS_ACTIVE},
+        },
+        user::{
+            dto::{
+                AcceptInvitationRequest, CreateInvitationRequest, InvitationResponse, UserProfile,
+            },
+            service::{self as user_service, NewUser, build_profile, ensure_email_available},
+        },
+    },
+};
+
+/// How long an invitation token stays valid after each send
+const INVITATION_TTL_DAYS: i64 = 7;
+
+/// Invite an email address with a preselected role on behalf of an admin
+pub async fn create_invitation(
+    db: &DatabaseConnection,
+    mailer: &dyn Mailer,
+    ctx: &RequestContext,
+    claims: &Claims,
+    mut req: CreateInvitationRequest,
+) -> Result<InvitationResponse> {
+    req.email = normalize_email(&req.email)?;
+    ensure_email_available(db, &req.email, None).await?;
+    let role = find_assignable_role(db, req.role_id, Some(claims)).await?;
+
+    if find_open_by_email(db, &req.email).await?.is_some() {
+        return Err(AppError::Conflict(
+            "An invitation is already pending for this email".to_string(),
+        ));
+    }
+
+    let txn = db.begin().await?;
+
+    let (invitation, token, recorded) =
+        insert_invitation(&txn, ctx, claims, req.email, &role).await?;
+
+    // Send before committing so a delivery failure leaves no unusable invitation
+    send_invitation(mailer, &invitation, &role, &token).await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    Ok(to_response(invitation, role))
+}
+
+/// Store an invitation for an address and audit it, returning the token to email
+///
+/// Callers check the address and role first; a race on the pending email
+/// index still fails with Conflict. Attach the returned audit reference once
+/// the transaction commits.
+pub(super) async fn insert_invitation<C: ConnectionTrait>(
+    db: &C,
+    ctx: &RequestContext,
+    claims: &Claims,
+    email: String,
+    role: &roles::Model,
+) -> Result<(invitations::Model, String, RecordedChange)> {
+    let token = generate_token();
+    let expires_at = chrono::Utc::now() + Duration::days(INVITATION_TTL_DAYS);
+
+    let invitation = invitations::ActiveModel {
+        email: Set(email),
+        role_id: Set(role.id),
+        token_hash: Set(hash_token(&token)),
+        invited_by: Set(Some(claims.sub)),
+        expires_at: Set(expires_at.into()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .map_err(map_open_invitation_violation)?;
+
+    let recorded = audit::record(
+        db,
+        ctx,
+        claims,
+        AuditEntry::new("user.invite", "invitation", invitation.id)
+            .change(EntityChange::created(&invitation))
+            .risk(RiskLevel::Medium),
+    )
+    .await?;
+
+    Ok((invitation, token, recorded))
+}
+
+/// List invitations that have been neither accepted nor revoked
+pub async fn list_pending(
+    db: &DatabaseConnection,
+    pagination: &PaginationParams,
+) -> Result<(Vec<InvitationResponse>, u64)> {
+    let select = invitations::Entity::find()
+        .filter(invitations::Column::AcceptedAt.is_null())
+        .filter(invitations::Column::RevokedAt.is_null());
+    let total = select.clone().count(db).await?;
+
+    let rows = select
+        .find_also_related(roles::Entity)
+        .order_by_desc(invitations::Column::CreatedAt)
+        .offset(pagination.offset())
+        .limit(pagination.limit())
+        .all(db)
+        .await?;
+
+    let items = rows
+        .into_iter()
+        .filter_map(|(invitation, role)| role.map(|role| to_response(invitation, role)))
+        .collect();
+
+    Ok((items, total))
+}
+
+/// Issue a fresh token for a pending invitation and email it again
+///
+/// The previous token stops working and the expiry restarts.
+pub async fn resend_invitation(
+    db: &DatabaseConnection,
+    mailer: &dyn Mailer,
+    ctx: &RequestContext,
+    claims: &Claims,
+    invitation_id: i32,
+) -> Result<InvitationResponse> {
+    let invitation = find_open(db, invitation_id).await?;
+    let role = find_assignable_role(db, invitation.role_id, Some(claims)).await?;
+
+    let token = generate_token();
+    let now = chrono::Utc::now();
+
+    let change = EntityChange::capture(&invitation);
+    let mut active: invitations::ActiveModel = invitation.into();
+    active.token_hash = Set(hash_token(&token));
+    active.expires_at = Set((now + Duration::days(INVITATION_TTL_DAYS)).into());
+    active.last_sent_at = Set(now.into());
+
+    let txn = db.begin().await?;
+    let invitation = active.update(&txn).await?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.resend_invitation", "invitation", invitation.id)
+            .change(change.updated(&invitation)),
+    )
+    .await?;
+
+    send_invitation(mailer, &invitation, &role, &token).await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    Ok(to_response(invitation, role))
+}
+
+/// Withdraw a pending invitation so its token can no longer be accepted
+pub async fn revoke_invitation(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    invitation_id: i32,
+) -> Result<()> {
+    let invitation = find_open(db, invitation_id).await?;
+
+    let change = EntityChange::capture(&invitation);
+    let mut active: invitations::ActiveModel = invitation.into();
+    active.revoked_at = Set(Some(chrono::Utc::now().into()));
+
+    let txn = db.begin().await?;
+    let invitation = active.update(&txn).await?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.revoke_invitation", "invitation", invitation.id)
+            .change(change.updated(&invitation)),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    Ok(())
+}
+
+/// Create the invitee's account with their chosen credentials
+///
+/// The account is active straight away since the token proves ownership of
+/// the invited address.
+pub async fn accept_invitation(
+    db: &DatabaseConnection,
+    req: AcceptInvitationRequest,
+) -> Result<UserProfile> {
+    let now = chrono::Utc::now();
+
+    let invitation = invitations::Entity::find()
+        .filter(invitations::Column::TokenHash.eq(hash_token(&req.token)))
+        .filter(invitations::Column::AcceptedAt.is_null())
+        .filter(invitations::Column::RevokedAt.is_null())
+        .one(db)
+        .await?
+        .filter(|invitation| invitation.expires_at > now)
+        .ok_or_else(|| AppError::BadRequest("Invalid or expired invitation".to_string()))?;
+
+    // The inviter's permissions were checked when the invitation was sent
+    let role = find_assignable_role(db, invitation.role_id, None).await?;
+
+    let txn = db.begin().await?;
+
+    // Claim the invitation first so concurrent accepts cannot both succeed
+    let claimed = invitations::Entity::update_many()
+        .col_expr(invitations::Column::AcceptedAt, Expr::value(now))
+        .filter(invitations::Column::Id.eq(invitation.id))
+        .filter(invitations::Column::AcceptedAt.is_null())
+        .filter(invitations::Column::RevokedAt.is_null())
+        .exec(&txn)
+        .await?;
+    if claimed.rows_affected == 0 {
+        return Err(AppError::BadRequest(
+            "Invalid or expired invitation".to_string(),
+        ));
+    }
+
+    let nickname = req.nickname.unwrap_or_else(|| req.username.clone());
+    let user = user_service::create_user(
+        &txn,
+        NewUser {
+            username: req.username,
+            email: invitation.email,
+            password: req.password,
+            nickname,
+            role_ids: vec![role.id],
+            password_change_required: false,
+        },
+    )
+    .await?;
+
+    invitations::Entity::update_many()
+        .col_expr(invitations::Column::AcceptedUserId, Expr::value(user.id))
+        .filter(invitations::Column::Id.eq(invitation.id))
+        .exec(&txn)
+        .await?;
+
+    txn.commit().await?;
+
+    build_profile(db, user).await
+}
+
+/// Load a pending invitation or fail with NotFound
+async fn find_open(db: &DatabaseConnection, invitation_id: i32) -> Result<invitations::Model> {
+    invitations::Entity::find_by_id(invitation_id)
+        .filter(invitations::Column::AcceptedAt.is_null())
+        .filter(invitations::Column::RevokedAt.is_null())
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invitation not found".to_string()))
+}
+
+/// Pending invitation for an address, compared case-insensitively
+async fn find_open_by_email(
+    db: &DatabaseConnection,
+    email: &str,
+) -> Result<Option<invitations::Model>> {
+    let invitation = invitations::Entity::find()
+        .filter(Expr::cust_with_values("lower(email) = lower($1)", [email]))
+        .filter(invitations::Column::AcceptedAt.is_null())
+        .filter(invitations::Column::RevokedAt.is_null())
+        .one(db)
+        .await?;
+
+    Ok(invitation)
+}
+
+/// Load a role that invitations may grant, failing with BadRequest otherwise
+///
+/// With `granted_by`, also refuses roles carrying permissions that caller does not hold.
+async fn find_assignable_role(
+    db: &DatabaseConnection,
+    role_id: i32,
+    granted_by: Option<&Claims>,
+) -> Result<roles::Model> {
+    let role = roles::Entity::find_by_id(role_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::BadRequest("Unknown role ID".to_string()))?;
+
+    if role.status != ROLE_STATUS_ACTIVE {
+        return Err(AppError::BadRequest("Role is disabled".to_string()));
+    }
+    if let Some(claims) = granted_by {
+        role_service::ensure_grantable(db, claims, &[role.id]).await?;
+    }
+
+    Ok(role)
+}
+
+/// Email the invitee their acceptance token
+pub(super) async fn send_invitation(
+    mailer: &dyn Mailer,
+    invitation: &invitations::Model,
+    role: &roles::Model,
+    token: &str,
+) -> Result<()> {
+    mailer
+        .send(Email::new(
+            &invitation.email,
+            "You have been invited",
+            format!(
+                "Hi,\n\nYou have been invited to create an account with the {} role.\n\n\
+                 Use this token to accept the invitation and choose your username and password:\n\n\
+                 {}\n\nIt expires in {} days.",
+                role.name, token, INVITATION_TTL_DAYS
+            ),
+        ))
+        .await
+}
+
+/// Map a race on the one-open-invitation-per-email index to Conflict
+fn map_open_invitation_violation(e: DbErr) -> AppError {
+    match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            AppError::Conflict("An invitation is already pending for this email".to_string())
+        }
+        _ => AppError::from(e),
+    }
+}
+
+/// Build response with the granted role and whether the token has lapsed
+fn to_response(invitation: invitations::Model, role: roles::Model) -> InvitationResponse {
+    let expires_at: chrono::DateTime<chrono::Utc> = invitation.expires_at.into();
+
+    InvitationResponse {
+        id: invitation.id,
+        email: invitation.email,
+        role: RoleSummary::from(role),
+        invited_by: invitation.invited_by,
+        expired: expires_at <= chrono::Utc::now(),
+        expires_at,
+        last_sent_at: invitation.last_sent_at.into(),
+        created_at: invitation.created_at.into(),
+    }
+}
@@ -0,0 +1,99 @@
+use sea_orm::*;
+
+use crate::{
+    common::{
+        RequestContext,
+        errors::{AppError, Result},
+        jwt::Claims,
+    },
+    entity::{sea_orm_active_enums::UserStatus, users},
+    modules::audit::{
+        change::{EntityChange, RecordedChange},
+        service::{self as audit, AuditEntry, RiskLevel},
+    },
+};
+
+impl UserStatus {
+    /// Name used in API payloads and exports
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserStatus::Suspended => "suspended",
+            UserStatus::Active => "active",
+            UserStatus::PendingVerification => "pending_verification",
+            UserStatus::Banned => "banned",
+            UserStatus::Deleted => "deleted",
+        }
+    }
+
+    /// Whether a user in this status may move to `next`
+    ///
+    /// Deleted is terminal; everything else can be deleted.
+    pub fn can_transition_to(self, next: UserStatus) -> bool {
+        use UserStatus::*;
+
+        matches!(
+            (self, next),
+            (PendingVerification, Active | Suspended | Banned | Deleted)
+                | (Active, Suspended | Banned | Deleted)
+                | (Suspended, Active | Banned | Deleted)
+                | (Banned, Active | Suspended | Deleted)
+        )
+    }
+}
+
+/// Move a user to a new status after validating the transition, recording it in `audit_logs`
+///
+/// Keeps `banned_at` in step with the banned state and revokes issued
+/// tokens. Pass the transaction the change is part of so the audit row
+/// commits with it, and attach the returned reference once it has.
+pub async fn transition<C: ConnectionTrait>(
+    db: &C,
+    ctx: &RequestContext,
+    claims: &Claims,
+    user: users::Model,
+    next: UserStatus,
+    reason: Option<String>,
+) -> Result<(users::Model, RecordedChange)> {
+    let current = user.status;
+    if !current.can_transition_to(next) {
+        return Err(AppError::Conflict(format!(
+            "Cannot change user status from {} to {}",
+            current.as_str(),
+            next.as_str()
+        )));
+    }
+
+    let now = chrono::Utc::now();
+    let change = EntityChange::capture(&user);
+
+    let token_version = user.token_version;
+    let mut active: users::ActiveModel = user.into();
+    active.status = Set(next);
+    // Tokens issued before a suspension must not come back to life on reactivation
+    active.token_version = Set(token_version + 1);
+    if next == UserStatus::Banned {
+        active.banned_at = Set(Some(now.into()));
+    } else if current == UserStatus::Banned {
+        active.banned_at = Set(None);
+    }
+    active.updated_at = Set(now.into());
+    let user = active.update(db).await?;
+
+    let risk = match next {
+        UserStatus::Banned | UserStatus::Deleted => RiskLevel::High,
+        _ => RiskLevel::Medium,
+    };
+
+    let recorded = audit::record(
+        db,
+        ctx,
+        claims,
+        AuditEntry::new("user.status_transition", "user", user.id)
+            .change(change.updated(&user))
+            .reason(reason)
+            .risk(risk),
+    )
+    .await?;
+
+    Ok((user, recorded))
+}
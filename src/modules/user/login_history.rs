@@ -0,0 +1,188 @@
+use sea_orm::*;
+use woothee::parser::Parser;
+
+use crate::{
+    common::{
+        RequestContext,
+        errors::Result,
+        jwt::Claims,
+        pagination::PaginationParams,
+        policy::{Action, authorize},
+    },
+    entity::login_logs,
+    modules::{
+        audit::service::RiskLevel,
+        user::{dto::LoginHistoryItem, service::find_user},
+    },
+};
+
+/// Password checked, no second factor
+pub const METHOD_PASSWORD: &str = "password";
+
+/// Password followed by a code sent by SMS
+pub const METHOD_PASSWORD_SMS: &str = "password_sms";
+
+/// Status of an attempt that issued tokens
+const STATUS_SUCCESS: &str = "success";
+
+/// Status of an attempt that was refused
+const STATUS_FAILED: &str = "failed";
+
+/// Record a login attempt for a known user, rating it against earlier successful logins
+///
+/// A user agent or IP address never seen in a successful login counts as a
+/// new device or location. Failures to write are logged and never fail the
+/// login itself.
+pub async fn record(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    user_id: i32,
+    method: &str,
+    fail_reason: Option<&str>,
+) {
+    if let Err(e) = insert(db, ctx, user_id, method, fail_reason).await {
+        tracing::warn!("Failed to record login for user {}: {}", user_id, e);
+    }
+}
+
+/// Own login history, newest first
+pub async fn list(
+    db: &DatabaseConnection,
+    user_id: i32,
+    pagination: &PaginationParams,
+) -> Result<(Vec<LoginHistoryItem>, u64)> {
+    let select = login_logs::Entity::find().filter(login_logs::Column::UserId.eq(user_id));
+    let total = select.clone().count(db).await?;
+
+    let logs = select
+        .order_by_desc(login_logs::Column::LoginAt)
+        .order_by_desc(login_logs::Column::Id)
+        .offset(pagination.offset())
+        .limit(pagination.limit())
+        .all(db)
+        .await?;
+
+    Ok((logs.into_iter().map(to_item).collect(), total))
+}
+
+/// Another user's login history on behalf of an admin
+pub async fn list_for_user(
+    db: &DatabaseConnection,
+    claims: &Claims,
+    user_id: i32,
+    pagination: &PaginationParams,
+) -> Result<(Vec<LoginHistoryItem>, u64)> {
+    let user = find_user(db, user_id).await?;
+
+    authorize(claims, Action::Read, &user)?;
+
+    list(db, user.id, pagination).await
+}
+
+/// Write one login log row with new-device and new-location flags
+async fn insert(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    user_id: i32,
+    method: &str,
+    fail_reason: Option<&str>,
+) -> Result<()> {
+    let has_history = successful_logins(user_id).one(db).await?.is_some();
+    let is_new_device =
+        !seen_before(db, user_id, login_logs::Column::UserAgent, &ctx.user_agent).await?;
+    let is_new_location =
+        !seen_before(db, user_id, login_logs::Column::IpAddress, &ctx.ip_address).await?;
+
+    // Without a successful login there is nothing to compare against
+    let risk = match (has_history, is_new_device, is_new_location) {
+        (false, _, _) | (true, false, false) => RiskLevel::Low,
+        (true, true, true) => RiskLevel::High,
+        _ => RiskLevel::Medium,
+    };
+
+    login_logs::ActiveModel {
+        user_id: Set(Some(user_id)),
+        login_method: Set(Some(method.to_string())),
+        ip_address: Set(ctx.ip_address.clone()),
+        user_agent: Set(ctx.user_agent.clone()),
+        status: Set(if fail_reason.is_some() {
+            STATUS_FAILED
+        } else {
+            STATUS_SUCCESS
+        }
+        .to_string()),
+        fail_reason: Set(fail_reason.map(str::to_string)),
+        risk_level: Set(Some(risk.as_str().to_string())),
+        is_new_device: Set(Some(is_new_device)),
+        is_new_location: Set(Some(is_new_location)),
+        login_at: Set(Some(chrono::Utc::now().naive_utc())),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(())
+}
+
+/// Successful logins of a user
+fn successful_logins(user_id: i32) -> Select<login_logs::Entity> {
+    login_logs::Entity::find()
+        .filter(login_logs::Column::UserId.eq(user_id))
+        .filter(login_logs::Column::Status.eq(STATUS_SUCCESS))
+}
+
+/// Whether a successful login already came with this value; unknown values count as seen
+async fn seen_before(
+    db: &DatabaseConnection,
+    user_id: i32,
+    column: login_logs::Column,
+    value: &Option<String>,
+) -> Result<bool> {
+    let Some(value) = value else {
+        return Ok(true);
+    };
+
+    let seen = successful_logins(user_id)
+        .filter(column.eq(value.as_str()))
+        .one(db)
+        .await?
+        .is_some();
+
+    Ok(seen)
+}
+
+/// Readable browser and OS, e.g. `Chrome on Windows 10`
+fn describe_device(user_agent: &str) -> Option<String> {
+    let parsed = Parser::new().parse(user_agent)?;
+    if parsed.name == "UNKNOWN" {
+        return None;
+    }
+
+    match parsed.os {
+        "UNKNOWN" => Some(parsed.name.to_string()),
+        os => Some(format!("{} on {}", parsed.name, os)),
+    }
+}
+
+/// Build history item, flagging high-risk logins and failures from unfamiliar places
+fn to_item(log: login_logs::Model) -> LoginHistoryItem {
+    let failed = log.status == STATUS_FAILED;
+    let unfamiliar = log.is_new_device == Some(true) || log.is_new_location == Some(true);
+    let high_risk = log.risk_level.as_deref() == Some(RiskLevel::High.as_str());
+
+    LoginHistoryItem {
+        id: log.id,
+        login_at: log.login_at.map(|at| at.and_utc()),
+        ip_address: log.ip_address,
+        location: log.location,
+        device: log.user_agent.as_deref().and_then(describe_device),
+        user_agent: log.user_agent,
+        method: log.login_method,
+        status: log.status,
+        fail_reason: log.fail_reason,
+        risk_level: log.risk_level,
+        is_new_device: log.is_new_device.unwrap_or(false),
+        is_new_location: log.is_new_location.unwrap_or(false),
+        suspicious: high_risk || (failed && unfamiliar),
+    }
+}
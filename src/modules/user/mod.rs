@@ -1,3 +1,16 @@
+pub mod avatar;
+pub mod deletion;
 pub mod dto;
+pub mod export;
 pub mod handlers;
+pub mod import;
+pub mod invitation;
+pub mod lifecycle;
+pub mod login_history;
+pub mod permissions;
+pub mod phone;
+pub mod policy;
+pub mod preferences;
 pub mod service;
+pub mod spreadsheet;
+pub mod username;
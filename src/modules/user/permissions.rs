@@ -0,0 +1,169 @@
+use axum::http::Method;
+
+use crate::middleware::RoutePermission;
+
+/// List all users
+pub static LIST: RoutePermission = RoutePermission {
+    slug: "user:list",
+    name: "List users",
+    method: Method::GET,
+    path: "/users",
+};
+
+/// Fuzzy search users
+pub static SEARCH: RoutePermission = RoutePermission {
+    slug: "user:search",
+    name: "Search users",
+    method: Method::GET,
+    path: "/users/search",
+};
+
+/// Export users as a spreadsheet
+pub static EXPORT: RoutePermission = RoutePermission {
+    slug: "user:export",
+    name: "Export users",
+    method: Method::GET,
+    path: "/users/export",
+};
+
+/// View any user's profile
+pub static READ: RoutePermission = RoutePermission {
+    slug: "user:read",
+    name: "View user",
+    method: Method::GET,
+    path: "/users/:id",
+};
+
+/// View any user's login history
+pub static LOGIN_HISTORY: RoutePermission = RoutePermission {
+    slug: "user:login_history",
+    name: "View user login history",
+    method: Method::GET,
+    path: "/users/:id/login-history",
+};
+
+/// Look up a user by current or reserved previous username
+pub static LOOKUP: RoutePermission = RoutePermission {
+    slug: "user:lookup",
+    name: "Look up user by username",
+    method: Method::GET,
+    path: "/users/by-username/:username",
+};
+
+/// Create users on behalf of others
+pub static CREATE: RoutePermission = RoutePermission {
+    slug: "user:create",
+    name: "Create user",
+    method: Method::POST,
+    path: "/users",
+};
+
+/// Edit any user's attributes
+pub static UPDATE: RoutePermission = RoutePermission {
+    slug: "user:update",
+    name: "Update user",
+    method: Method::PATCH,
+    path: "/users/:id",
+};
+
+/// Delete users
+pub static DELETE: RoutePermission = RoutePermission {
+    slug: "user:delete",
+    name: "Delete user",
+    method: Method::DELETE,
+    path: "/users/:id",
+};
+
+/// Bulk create users from a CSV file
+pub static IMPORT: RoutePermission = RoutePermission {
+    slug: "user:import",
+    name: "Import users",
+    method: Method::POST,
+    path: "/users/import",
+};
+
+/// List invitations awaiting acceptance
+pub static LIST_INVITATIONS: RoutePermission = RoutePermission {
+    slug: "user:list_invitations",
+    name: "List invitations",
+    method: Method::GET,
+    path: "/users/invitations",
+};
+
+/// Invite someone by email with a preselected role
+pub static INVITE: RoutePermission = RoutePermission {
+    slug: "user:invite",
+    name: "Invite user",
+    method: Method::POST,
+    path: "/users/invitations",
+};
+
+/// Send a pending invitation again with a fresh token
+pub static RESEND_INVITATION: RoutePermission = RoutePermission {
+    slug: "user:resend_invitation",
+    name: "Resend invitation",
+    method: Method::POST,
+    path: "/users/invitations/:id/resend",
+};
+
+/// Withdraw a pending invitation
+pub static REVOKE_INVITATION: RoutePermission = RoutePermission {
+    slug: "user:revoke_invitation",
+    name: "Revoke invitation",
+    method: Method::DELETE,
+    path: "/users/invitations/:id",
+};
+
+/// Cancel a scheduled account deletion
+pub static CANCEL_DELETION: RoutePermission = RoutePermission {
+    slug: "user:cancel_deletion",
+    name: "Cancel user deletion",
+    method: Method::POST,
+    path: "/users/:id/deletion/cancel",
+};
+
+/// Replace a user's roles
+pub static ASSIGN_ROLES: RoutePermission = RoutePermission {
+    slug: "user:assign_roles",
+    name: "Assign user roles",
+    method: Method::PUT,
+    path: "/users/:id/roles",
+};
+
+/// Move users between lifecycle states
+pub static UPDATE_STATUS: RoutePermission = RoutePermission {
+    slug: "user:update_status",
+    name: "Change user status",
+    method: Method::PATCH,
+    path: "/users/:id/status",
+};
+
+/// Reset a user's password to a temporary one
+pub static RESET_PASSWORD: RoutePermission = RoutePermission {
+    slug: "user:reset_password",
+    name: "Reset user password",
+    method: Method::POST,
+    path: "/users/:id/password-reset",
+};
+
+/// Every route permission declared by the user module
+pub static ALL: &[&RoutePermission] = &[
+    &LIST,
+    &SEARCH,
+    &EXPORT,
+    &READ,
+    &LOGIN_HISTORY,
+    &LOOKUP,
+    &CREATE,
+    &IMPORT,
+    &LIST_INVITATIONS,
+    &INVITE,
+    &RESEND_INVITATION,
+    &REVOKE_INVITATION,
+    &UPDATE,
+    &DELETE,
+    &CANCEL_DELETION,
+    &ASSIGN_ROLES,
+    &UPDATE_STATUS,
+    &RESET_PASSWORD,
+];
@@ -0,0 +1,415 @@
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{sea_query::Expr, *};
+
+use crate::{
+    common::{
+        RequestContext,
+        errors::{AppError, Result},
+        jwt::Claims,
+        password::verify_password,
+        sms::{Sms, SmsProvider},
+        token::{generate_otp, hash_token},
+        validator::normalize_phone,
+    },
+    entity::{phone_verifications, users},
+    modules::{
+        audit::{
+            change::EntityChange,
+            service::{self as audit, AuditEntry, RiskLevel},
+        },
+        user::{
+            dto::{
+                AddPhoneRequest, PhoneVerificationPending, RemovePhoneRequest, UpdateSmsMfaRequest,
+                UserProfile, VerifyPhoneRequest,
+            },
+            service::{build_profile, find_user},
+        },
+    },
+};
+
+/// How long an SMS code stays valid
+pub const CODE_TTL_MINUTES: i64 = 10;
+
+/// Wrong guesses allowed before a code stops working
+const MAX_CODE_ATTEMPTS: i32 = 5;
+
+/// Minimum wait before another code of the same kind is sent to a user
+const RESEND_INTERVAL_SECONDS: i64 = 60;
+
+/// Code confirming a new phone number
+const PURPOSE_VERIFY: &str = "verify";
+
+/// Code answering an SMS login challenge
+const PURPOSE_LOGIN: &str = "login";
+
+/// Text a verification code to a new number for the caller
+///
+/// The number is only stored on the account once the code is confirmed.
+pub async fn request_verification(
+    db: &DatabaseConnection,
+    sms: &dyn SmsProvider,
+    claims: &Claims,
+    req: AddPhoneRequest,
+) -> Result<PhoneVerificationPending> {
+    let user = find_user(db, claims.sub).await?;
+
+    if !verify_password(&req.password, &user.password)? {
+        return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+    }
+
+    let phone = normalize_phone(&req.phone)?;
+    if user.phone.as_deref() == Some(phone.as_str()) {
+        return Err(AppError::BadRequest(
+            "Phone number is already verified".to_string(),
+        ));
+    }
+    ensure_phone_available(db, &phone, user.id).await?;
+
+    let expires_at = issue_code(db, sms, user.id, &phone, PURPOSE_VERIFY).await?;
+
+    Ok(PhoneVerificationPending { phone, expires_at })
+}
+
+/// Store the pending number on the caller's account once its code is confirmed
+pub async fn confirm_verification(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    req: VerifyPhoneRequest,
+) -> Result<UserProfile> {
+    let user = find_user(db, claims.sub).await?;
+    let verification = check_code(db, user.id, PURPOSE_VERIFY, &req.code).await?;
+
+    ensure_phone_available(db, &verification.phone, user.id).await?;
+
+    let now = chrono::Utc::now();
+    let txn = db.begin().await?;
+
+    claim_code(&txn, verification.id).await?;
+
+    let change = EntityChange::capture(&user);
+    let mut active: users::ActiveModel = user.into();
+    active.phone = Set(Some(verification.phone));
+    active.phone_verified_at = Set(Some(now.into()));
+    active.updated_at = Set(now.into());
+    let user = active.update(&txn).await.map_err(map_phone_violation)?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.verify_phone", "user", user.id)
+            .change(change.updated(&user))
+            .risk(RiskLevel::Medium),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    build_profile(db, user).await
+}
+
+/// Remove the caller's phone number, which also turns off SMS login codes
+pub async fn remove_phone(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    req: RemovePhoneRequest,
+) -> Result<UserProfile> {
+    let user = find_user(db, claims.sub).await?;
+
+    if !verify_password(&req.password, &user.password)? {
+        return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+    }
+    if user.phone.is_none() {
+        return Err(AppError::BadRequest(
+            "No phone number on the account".to_string(),
+        ));
+    }
+
+    let txn = db.begin().await?;
+
+    phone_verifications::Entity::delete_many()
+        .filter(phone_verifications::Column::UserId.eq(user.id))
+        .filter(phone_verifications::Column::UsedAt.is_null())
+        .exec(&txn)
+        .await?;
+
+    let change = EntityChange::capture(&user);
+    let mut active: users::ActiveModel = user.into();
+    active.phone = Set(None);
+    active.phone_verified_at = Set(None);
+    active.sms_mfa_enabled = Set(false);
+    active.updated_at = Set(chrono::Utc::now().into());
+    let user = active.update(&txn).await?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.remove_phone", "user", user.id)
+            .change(change.updated(&user))
+            .risk(RiskLevel::High),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    build_profile(db, user).await
+}
+
+/// Turn SMS login codes on or off for the caller
+pub async fn update_sms_mfa(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    req: UpdateSmsMfaRequest,
+) -> Result<UserProfile> {
+    let user = find_user(db, claims.sub).await?;
+
+    if !verify_password(&req.password, &user.password)? {
+        return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+    }
+    if req.enabled && user.phone.is_none() {
+        return Err(AppError::BadRequest(
+            "Verify a phone number before enabling SMS codes".to_string(),
+        ));
+    }
+    if req.enabled == user.sms_mfa_enabled {
+        return build_profile(db, user).await;
+    }
+
+    // Turning a second factor off weakens the account more than turning it on
+    let risk = if req.enabled {
+        RiskLevel::Medium
+    } else {
+        RiskLevel::High
+    };
+
+    let txn = db.begin().await?;
+
+    let change = EntityChange::capture(&user);
+    let mut active: users::ActiveModel = user.into();
+    active.sms_mfa_enabled = Set(req.enabled);
+    active.updated_at = Set(chrono::Utc::now().into());
+    let user = active.update(&txn).await?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.update_sms_mfa", "user", user.id)
+            .change(change.updated(&user))
+            .risk(risk),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    build_profile(db, user).await
+}
+
+/// Find a user by verified phone number; input that is not a phone number matches nobody
+pub async fn find_by_phone<C: ConnectionTrait>(
+    db: &C,
+    phone: &str,
+) -> Result<Option<users::Model>> {
+    let Ok(phone) = normalize_phone(phone) else {
+        return Ok(None);
+    };
+
+    let user = users::Entity::find()
+        .filter(users::Column::Phone.eq(phone))
+        .one(db)
+        .await?;
+
+    Ok(user)
+}
+
+/// Text a login code to the user's verified phone, returning when it expires
+pub async fn send_login_code(
+    db: &DatabaseConnection,
+    sms: &dyn SmsProvider,
+    user: &users::Model,
+) -> Result<DateTime<Utc>> {
+    let phone = user
+        .phone
+        .as_deref()
+        .ok_or_else(|| AppError::BadRequest("No phone number on the account".to_string()))?;
+
+    issue_code(db, sms, user.id, phone, PURPOSE_LOGIN).await
+}
+
+/// Check and use up a login code sent by [`send_login_code`]
+pub async fn verify_login_code(db: &DatabaseConnection, user_id: i32, code: &str) -> Result<()> {
+    let verification = check_code(db, user_id, PURPOSE_LOGIN, code).await?;
+
+    claim_code(db, verification.id).await
+}
+
+/// Show only the country code prefix and last digits of a number
+pub fn mask_phone(phone: &str) -> String {
+    let visible = phone.len().saturating_sub(4);
+
+    phone
+        .char_indices()
+        .map(|(i, c)| if i == 0 || i >= visible { c } else { '*' })
+        .collect()
+}
+
+/// Fail with Conflict when another account has verified this number
+async fn ensure_phone_available(db: &DatabaseConnection, phone: &str, user_id: i32) -> Result<()> {
+    let taken = users::Entity::find()
+        .filter(users::Column::Phone.eq(phone))
+        .filter(users::Column::Id.ne(user_id))
+        .one(db)
+        .await?;
+
+    if taken.is_some() {
+        return Err(AppError::Conflict(
+            "Phone number is already in use".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Replace any open code of this kind with a fresh one and text it
+async fn issue_code(
+    db: &DatabaseConnection,
+    sms: &dyn SmsProvider,
+    user_id: i32,
+    phone: &str,
+    purpose: &str,
+) -> Result<DateTime<Utc>> {
+    let now = chrono::Utc::now();
+
+    let recent = phone_verifications::Entity::find()
+        .filter(phone_verifications::Column::UserId.eq(user_id))
+        .filter(phone_verifications::Column::Purpose.eq(purpose))
+        .filter(
+            phone_verifications::Column::CreatedAt
+                .gt(now - Duration::seconds(RESEND_INTERVAL_SECONDS)),
+        )
+        .one(db)
+        .await?;
+    if recent.is_some() {
+        return Err(AppError::BadRequest(
+            "A code was sent recently, please wait before requesting another".to_string(),
+        ));
+    }
+
+    let code = generate_otp();
+    let expires_at = now + Duration::minutes(CODE_TTL_MINUTES);
+
+    let txn = db.begin().await?;
+
+    // Only the latest code can be used
+    phone_verifications::Entity::delete_many()
+        .filter(phone_verifications::Column::UserId.eq(user_id))
+        .filter(phone_verifications::Column::Purpose.eq(purpose))
+        .filter(phone_verifications::Column::UsedAt.is_null())
+        .exec(&txn)
+        .await?;
+
+    phone_verifications::ActiveModel {
+        user_id: Set(user_id),
+        phone: Set(phone.to_string()),
+        purpose: Set(purpose.to_string()),
+        code_hash: Set(hash_token(&code)),
+        expires_at: Set(expires_at.into()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    // Send before committing so a delivery failure leaves no dangling code
+    sms.send(Sms::new(
+        phone,
+        format!(
+            "Your verification code is {}. It expires in {} minutes.",
+            code, CODE_TTL_MINUTES
+        ),
+    ))
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(expires_at)
+}
+
+/// Match a code against the user's latest open one, counting wrong guesses
+async fn check_code(
+    db: &DatabaseConnection,
+    user_id: i32,
+    purpose: &str,
+    code: &str,
+) -> Result<phone_verifications::Model> {
+    let now = chrono::Utc::now();
+    let invalid = || AppError::BadRequest("Invalid or expired code".to_string());
+
+    let verification = phone_verifications::Entity::find()
+        .filter(phone_verifications::Column::UserId.eq(user_id))
+        .filter(phone_verifications::Column::Purpose.eq(purpose))
+        .filter(phone_verifications::Column::UsedAt.is_null())
+        .order_by_desc(phone_verifications::Column::CreatedAt)
+        .one(db)
+        .await?
+        .filter(|v| v.expires_at > now && v.attempts < MAX_CODE_ATTEMPTS)
+        .ok_or_else(invalid)?;
+
+    if verification.code_hash != hash_token(code.trim()) {
+        phone_verifications::Entity::update_many()
+            .col_expr(
+                phone_verifications::Column::Attempts,
+                Expr::col(phone_verifications::Column::Attempts).add(1),
+            )
+            .filter(phone_verifications::Column::Id.eq(verification.id))
+            .exec(db)
+            .await?;
+        return Err(invalid());
+    }
+
+    Ok(verification)
+}
+
+/// Mark a code used so concurrent requests cannot both redeem it
+async fn claim_code<C: ConnectionTrait>(db: &C, verification_id: i32) -> Result<()> {
+    let claimed = phone_verifications::Entity::update_many()
+        .col_expr(
+            phone_verifications::Column::UsedAt,
+            Expr::value(chrono::Utc::now()),
+        )
+        .filter(phone_verifications::Column::Id.eq(verification_id))
+        .filter(phone_verifications::Column::UsedAt.is_null())
+        .exec(db)
+        .await?;
+    if claimed.rows_affected == 0 {
+        return Err(AppError::BadRequest("Invalid or expired code".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Map a race on the unique phone index to Conflict
+fn map_phone_violation(e: DbErr) -> AppError {
+    match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            AppError::Conflict("Phone number is already in use".to_string())
+        }
+        _ => AppError::from(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn masks_all_but_prefix_and_last_digits() {
+        assert_eq!(mask_phone("+14155550123"), "+*******0123");
+    }
+}
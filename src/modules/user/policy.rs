@@ -0,0 +1,61 @@
+use crate::{
+    common::{
+        errors::{AppError, Result},
+        jwt::Claims,
+        policy::{Action, Resource, permission_slug},
+    },
+    entity::{sea_orm_active_enums::UserStatus, sessions, users},
+};
+
+/// Users may read and edit their own profile; anything else needs `user:*` permissions
+impl Resource for users::Model {
+    const KIND: &'static str = "user";
+    const OWNER_ACTIONS: &'static [Action] = &[Action::Read, Action::Update];
+
+    fn owner_id(&self) -> Option<i32> {
+        Some(self.id)
+    }
+
+    fn check_attributes(&self, claims: &Claims, action: Action) -> Result<()> {
+        // Erased accounts are kept only as anonymized FK targets
+        if matches!(action, Action::Update | Action::Delete) && self.status == UserStatus::Deleted {
+            return Err(AppError::Conflict("Account has been deleted".to_string()));
+        }
+
+        // Admins cannot remove their own account through user management
+        if action == Action::Delete && self.id == claims.sub {
+            return Err(AppError::Forbidden(
+                "Cannot delete your own account".to_string(),
+            ));
+        }
+
+        // Banned accounts keep read access to their profile but cannot change it themselves
+        if action == Action::Update
+            && self.status == UserStatus::Banned
+            && !claims.has_permission(&permission_slug(Self::KIND, Action::Update))
+        {
+            return Err(AppError::Forbidden("Account is banned".to_string()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Users may list and revoke their own sessions; anything else needs `session:*` permissions
+impl Resource for sessions::Model {
+    const KIND: &'static str = "session";
+    const OWNER_ACTIONS: &'static [Action] = &[Action::Read, Action::Delete];
+
+    fn owner_id(&self) -> Option<i32> {
+        Some(self.user_id)
+    }
+
+    fn check_attributes(&self, _claims: &Claims, action: Action) -> Result<()> {
+        // Revoked sessions are kept for history and can no longer change
+        if action == Action::Update && self.revoked_at.is_some() {
+            return Err(AppError::Conflict("Session already revoked".to_string()));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use chrono::{DateTime, Utc};
+use sea_orm::*;
+use serde_json::{Map, Value};
+
+use crate::{
+    common::{
+        RequestContext,
+        errors::{AppError, Result},
+        jwt::Claims,
+        validator::{validate_locale, validate_timezone},
+    },
+    entity::user_preferences,
+    modules::{
+        audit::{
+            change::EntityChange,
+            service::{self as audit, AuditEntry},
+        },
+        user::dto::{DateFormat, UpdatePreferencesRequest, UserPreferences},
+    },
+};
+
+impl DateFormat {
+    /// strftime pattern for this layout
+    pub fn pattern(&self) -> &'static str {
+        match self {
+            DateFormat::YearMonthDay => "%Y-%m-%d",
+            DateFormat::DayMonthYear => "%d/%m/%Y",
+            DateFormat::MonthDayYear => "%m/%d/%Y",
+            DateFormat::DayMonthYearDotted => "%d.%m.%Y",
+        }
+    }
+}
+
+impl UserPreferences {
+    /// Render a timestamp in the user's time zone and date layout, for emails
+    pub fn format_datetime(&self, at: DateTime<Utc>) -> String {
+        let timezone: chrono_tz::Tz = self.timezone.parse().unwrap_or(chrono_tz::UTC);
+        let local = at.with_timezone(&timezone);
+
+        format!(
+            "{} {} {}",
+            local.format(self.date_format.pattern()),
+            local.format("%H:%M"),
+            timezone.name()
+        )
+    }
+}
+
+/// Load a user's preferences, filling anything unset or unreadable with defaults
+///
+/// Stored values are applied field by field, so one value that no longer
+/// matches the schema falls back alone instead of resetting everything.
+pub async fn load<C: ConnectionTrait>(db: &C, user_id: i32) -> Result<UserPreferences> {
+    let stored = user_preferences::Entity::find_by_id(user_id)
+        .one(db)
+        .await?;
+
+    let mut document = serde_json::to_value(UserPreferences::default())
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+    if let Some(Value::Object(fields)) = stored.map(|row| row.preferences) {
+        merge_valid(&mut document, "", fields, user_id);
+    }
+
+    serde_json::from_value(document).map_err(|e| AppError::Internal(e.to_string()))
+}
+
+/// Copy stored fields into `document`, skipping any that would make it unreadable
+///
+/// Nested objects are merged key by key; keys the schema does not declare are dropped.
+fn merge_valid(document: &mut Value, prefix: &str, fields: Map<String, Value>, user_id: i32) {
+    for (key, value) in fields {
+        let pointer = format!("{}/{}", prefix, key.replace('~', "~0").replace('/', "~1"));
+
+        match (document.pointer(&pointer), value) {
+            (Some(Value::Object(_)), Value::Object(nested)) => {
+                merge_valid(document, &pointer, nested, user_id)
+            }
+            (Some(_), value) => {
+                let Some(slot) = document.pointer_mut(&pointer) else {
+                    continue;
+                };
+                let previous = std::mem::replace(slot, value);
+
+                if let Err(e) = serde_json::from_value::<UserPreferences>(document.clone()) {
+                    tracing::warn!(
+                        "Ignoring invalid preference '{}' of user {}: {}",
+                        pointer,
+                        user_id,
+                        e
+                    );
+                    if let Some(slot) = document.pointer_mut(&pointer) {
+                        *slot = previous;
+                    }
+                }
+            }
+            (None, _) => {}
+        }
+    }
+}
+
+/// Apply a partial update to the caller's preferences
+pub async fn update(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    req: UpdatePreferencesRequest,
+) -> Result<UserPreferences> {
+    if let Some(locale) = &req.locale {
+        validate_locale(locale)?;
+    }
+    if let Some(timezone) = &req.timezone {
+        validate_timezone(timezone)?;
+    }
+
+    let before = load(db, claims.sub).await?;
+    let mut after = before.clone();
+
+    if let Some(locale) = req.locale {
+        after.locale = locale;
+    }
+    if let Some(timezone) = req.timezone {
+        after.timezone = timezone;
+    }
+    if let Some(date_format) = req.date_format {
+        after.date_format = date_format;
+    }
+    if let Some(theme) = req.theme {
+        after.theme = theme;
+    }
+    if let Some(notifications) = req.notifications {
+        if let Some(security_alerts) = notifications.security_alerts {
+            after.notifications.security_alerts = security_alerts;
+        }
+        if let Some(account_updates) = notifications.account_updates {
+            after.notifications.account_updates = account_updates;
+        }
+        if let Some(product_news) = notifications.product_news {
+            after.notifications.product_news = product_news;
+        }
+    }
+
+    let document = serde_json::to_value(&after).map_err(|e| AppError::Internal(e.to_string()))?;
+
+    let txn = db.begin().await?;
+
+    let row = user_preferences::ActiveModel {
+        user_id: Set(claims.sub),
+        preferences: Set(document),
+        updated_at: Set(Utc::now().into()),
+    };
+    user_preferences::Entity::insert(row)
+        .on_conflict(
+            sea_query::OnConflict::column(user_preferences::Column::UserId)
+                .update_columns([
+                    user_preferences::Column::Preferences,
+                    user_preferences::Column::UpdatedAt,
+                ])
+                .to_owned(),
+        )
+        .exec(&txn)
+        .await?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.update_preferences", "user", claims.sub)
+            .change(EntityChange::capture(&before).updated(&after)),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    Ok(after)
+}
@@ -1,42 +1,976 @@
-use sea_orm::*;
+use sea_orm::{
+    sea_query::{Expr, Func, LikeExpr, SimpleExpr, extension::postgres::PgExpr},
+    *,
+};
+
+use std::collections::HashSet;
+
+use chrono::Duration;
 
 use crate::{
-    common::errors::{AppError, Result},
-    entity::users,
-    modules::user::dto::{UserListItem, UserProfile},
+    common::{
+        RequestContext,
+        errors::{AppError, Result},
+        jwt::Claims,
+        mailer::{Email, Mailer},
+        pagination::PaginationParams,
+        password::{generate_temporary_password, hash_password, verify_password},
+        permission_cache::{Invalidation, PermissionCache},
+        policy::{Action, authorize},
+        storage::Storage,
+        token::{generate_token, hash_token},
+        validator::normalize_email,
+    },
+    entity::{
+        email_change_requests, roles, sea_orm_active_enums::UserStatus, user_roles,
+        username_history, users,
+    },
+    modules::{
+        audit::{
+            change::EntityChange,
+            service::{self as audit, AuditEntry, RiskLevel},
+        },
+        role::service as role_service,
+        user::dto::{
+            AssignRolesRequest, AvatarResponse, AvatarThumbnail, ChangeEmailRequest,
+            ConfirmEmailChangeRequest, CreateUserRequest, EmailChangePending,
+            PasswordResetResponse, SearchHighlight, UpdateProfileRequest, UpdateUserRequest,
+            UpdateUserStatusRequest, UserListItem, UserListQuery, UserProfile, UserSearchQuery,
+            UserSearchResult, UserSortField,
+        },
+        user::{avatar, lifecycle, preferences, username},
+    },
 };
 
-/// Get user profile by ID
-pub async fn get_user_by_id(db: &DatabaseConnection, user_id: i32) -> Result<UserProfile> {
-    let user = users::Entity::find_by_id(user_id)
-        .one(db)
+/// Search results returned when no limit is given
+const DEFAULT_SEARCH_LIMIT: u64 = 20;
+
+/// How long an email change verification token stays valid
+const EMAIL_CHANGE_TOKEN_TTL_HOURS: i64 = 24;
+
+/// Validated input for creating a user account
+pub struct NewUser {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    pub nickname: String,
+    pub role_ids: Vec<i32>,
+    pub password_change_required: bool,
+}
+
+/// Create user with hashed password and role assignments
+///
+/// Shared by self-registration and admin creation so both enforce the same
+/// uniqueness rules; run inside a transaction to keep the roles consistent.
+pub async fn create_user<C: ConnectionTrait>(db: &C, new_user: NewUser) -> Result<users::Model> {
+    let email = normalize_email(&new_user.email)?;
+
+    let (username, username_skeleton) =
+        username::prepare_username(db, &new_user.username, None).await?;
+    ensure_email_available(db, &email, None).await?;
+    ensure_nickname_available(db, &new_user.nickname, None).await?;
+    ensure_roles_exist(db, &new_user.role_ids).await?;
+
+    // Hash password
+    let hashed_password = hash_password(&new_user.password)?;
+
+    let user = users::ActiveModel {
+        username: Set(username),
+        username_skeleton: Set(Some(username_skeleton)),
+        email: Set(email),
+        nickname: Set(new_user.nickname),
+        password: Set(hashed_password),
+        status: Set(UserStatus::Active),
+        password_change_required: Set(new_user.password_change_required),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    for role_id in new_user.role_ids {
+        role_service::assign_role(db, user.id, role_id).await?;
+    }
+
+    Ok(user)
+}
+
+/// Get user profile by ID on behalf of the authenticated caller
+pub async fn get_user_by_id(
+    db: &DatabaseConnection,
+    claims: &Claims,
+    user_id: i32,
+) -> Result<UserProfile> {
+    let user = find_user(db, user_id).await?;
+
+    authorize(claims, Action::Read, &user)?;
+
+    build_profile(db, user).await
+}
+
+/// Create user on behalf of an admin
+pub async fn admin_create_user(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    req: CreateUserRequest,
+) -> Result<UserProfile> {
+    role_service::ensure_grantable(db, claims, &req.role_ids).await?;
+
+    let txn = db.begin().await?;
+
+    let user = create_user(
+        &txn,
+        NewUser {
+            username: req.username,
+            email: req.email,
+            password: req.password,
+            nickname: req.nickname,
+            role_ids: req.role_ids,
+            password_change_required: req.password_change_required,
+        },
+    )
+    .await?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.create", "user", user.id).change(EntityChange::created(&user)),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    build_profile(db, user).await
+}
+
+/// Update user attributes on behalf of an admin
+pub async fn update_user(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    user_id: i32,
+    mut req: UpdateUserRequest,
+) -> Result<UserProfile> {
+    let user = find_user(db, user_id).await?;
+    authorize(claims, Action::Update, &user)?;
+    role_service::ensure_manageable(db, claims, user.id).await?;
+
+    req.email = req.email.as_deref().map(normalize_email).transpose()?;
+    if let Some(email) = &req.email {
+        ensure_email_available(db, email, Some(user.id)).await?;
+    }
+    if let Some(nickname) = &req.nickname {
+        ensure_nickname_available(db, nickname, Some(user.id)).await?;
+    }
+
+    let change = EntityChange::capture(&user);
+    let mut active: users::ActiveModel = user.into();
+    if let Some(email) = req.email {
+        active.email = Set(email);
+    }
+    if let Some(nickname) = req.nickname {
+        active.nickname = Set(nickname);
+    }
+    if let Some(avatar) = req.avatar {
+        active.avatar = Set(Some(avatar));
+    }
+    active.updated_at = Set(chrono::Utc::now().into());
+
+    let txn = db.begin().await?;
+    let user = active.update(&txn).await.map_err(map_unique_violation)?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.update", "user", user.id).change(change.updated(&user)),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    build_profile(db, user).await
+}
+
+/// Replace a user's roles on behalf of an admin
+pub async fn assign_roles(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    ctx: &RequestContext,
+    claims: &Claims,
+    user_id: i32,
+    req: AssignRolesRequest,
+) -> Result<UserProfile> {
+    let user = find_user(db, user_id).await?;
+    authorize(claims, Action::Update, &user)?;
+    role_service::ensure_manageable(db, claims, user.id).await?;
+
+    let role_ids: Vec<i32> = req
+        .role_ids
+        .into_iter()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    ensure_roles_exist(db, &role_ids).await?;
+    role_service::ensure_grantable(db, claims, &role_ids).await?;
+
+    let txn = db.begin().await?;
+
+    let before: Vec<i32> = role_service::user_roles(&txn, user.id)
         .await?
-        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+        .into_iter()
+        .map(|role| role.id)
+        .collect();
 
-    Ok(UserProfile {
-        id: user.id,
-        username: user.username,
-        email: user.email,
-        nickname: user.nickname,
-        avatar: user.avatar,
-        role_id: user.role_id,
-        status: user.status,
-    })
+    user_roles::Entity::delete_many()
+        .filter(user_roles::Column::UserId.eq(user.id))
+        .filter(user_roles::Column::RoleId.is_not_in(role_ids.iter().copied()))
+        .exec(&txn)
+        .await?;
+    for &role_id in &role_ids {
+        role_service::assign_role(&txn, user.id, role_id).await?;
+    }
+
+    let mut after = role_ids;
+    after.sort();
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.assign_roles", "user", user.id)
+            .before(serde_json::json!({ "role_ids": before }))
+            .after(serde_json::json!({ "role_ids": after }))
+            .risk(RiskLevel::Medium),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+    cache.invalidate(db, Invalidation::User(user.id)).await?;
+
+    build_profile(db, user).await
+}
+
+/// Move a user to another lifecycle status on behalf of an admin
+pub async fn update_status(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    ctx: &RequestContext,
+    claims: &Claims,
+    user_id: i32,
+    req: UpdateUserStatusRequest,
+) -> Result<UserProfile> {
+    let user = find_user(db, user_id).await?;
+    authorize(claims, Action::Update, &user)?;
+    role_service::ensure_manageable(db, claims, user.id).await?;
+
+    // Erasure must go through the deletion flow so PII is actually removed
+    if req.status == UserStatus::Deleted {
+        return Err(AppError::BadRequest(
+            "Use account deletion to delete users".to_string(),
+        ));
+    }
+
+    let txn = db.begin().await?;
+    let (user, recorded) =
+        lifecycle::transition(&txn, ctx, claims, user, req.status, req.reason).await?;
+    txn.commit().await?;
+    recorded.attach();
+    cache.invalidate(db, Invalidation::User(user.id)).await?;
+
+    build_profile(db, user).await
+}
+
+/// Reset a user's password to a temporary one that must be changed at next login
+pub async fn reset_password(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    ctx: &RequestContext,
+    claims: &Claims,
+    user_id: i32,
+) -> Result<PasswordResetResponse> {
+    let user = find_user(db, user_id).await?;
+    authorize(claims, Action::Update, &user)?;
+    role_service::ensure_manageable(db, claims, user.id).await?;
+
+    let temporary_password = generate_temporary_password();
+
+    let token_version = user.token_version;
+    let mut active: users::ActiveModel = user.into();
+    active.password = Set(hash_password(&temporary_password)?);
+    active.password_change_required = Set(true);
+    // Sign out every token issued with the old password
+    active.token_version = Set(token_version + 1);
+    active.updated_at = Set(chrono::Utc::now().into());
+
+    let txn = db.begin().await?;
+    let user = active.update(&txn).await?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.reset_password", "user", user.id).risk(RiskLevel::High),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+    cache.invalidate(db, Invalidation::User(user.id)).await?;
+
+    Ok(PasswordResetResponse { temporary_password })
 }
 
-/// Get list of all users
-pub async fn list_users(db: &DatabaseConnection) -> Result<Vec<UserListItem>> {
-    let users = users::Entity::find().all(db).await?;
+/// Get one page of users matching the filters, with the total match count
+pub async fn list_users(
+    db: &DatabaseConnection,
+    pagination: &PaginationParams,
+    query: &UserListQuery,
+) -> Result<(Vec<UserListItem>, u64)> {
+    let select = filter_users(users::Entity::find(), query);
+    let total = select.clone().count(db).await?;
+
+    let users = sort_users(select, query)
+        .offset(pagination.offset())
+        .limit(pagination.limit())
+        .all(db)
+        .await?;
 
-    Ok(users
+    let user_ids: Vec<i32> = users.iter().map(|user| user.id).collect();
+    let mut roles = role_service::roles_for_users(db, &user_ids).await?;
+
+    let items = users
         .into_iter()
         .map(|user| UserListItem {
             id: user.id,
             username: user.username,
             email: user.email,
             nickname: user.nickname,
-            role_id: user.role_id,
+            roles: roles.remove(&user.id).unwrap_or_default(),
             status: user.status,
         })
+        .collect();
+
+    Ok((items, total))
+}
+
+/// Update the caller's own nickname and avatar
+pub async fn update_profile(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    req: UpdateProfileRequest,
+) -> Result<UserProfile> {
+    let user = find_user(db, claims.sub).await?;
+
+    if let Some(nickname) = &req.nickname {
+        ensure_nickname_available(db, nickname, Some(user.id)).await?;
+    }
+
+    let change = EntityChange::capture(&user);
+    let mut active: users::ActiveModel = user.into();
+    if let Some(nickname) = req.nickname {
+        active.nickname = Set(nickname);
+    }
+    if let Some(avatar) = req.avatar {
+        active.avatar = Set(Some(avatar));
+    }
+    active.updated_at = Set(chrono::Utc::now().into());
+
+    let txn = db.begin().await?;
+    let user = active.update(&txn).await.map_err(|e| match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            AppError::Conflict("Nickname already exists".to_string())
+        }
+        _ => AppError::from(e),
+    })?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.update_profile", "user", user.id).change(change.updated(&user)),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    build_profile(db, user).await
+}
+
+/// Replace the caller's avatar with thumbnails generated from an uploaded image
+pub async fn upload_avatar(
+    db: &DatabaseConnection,
+    storage: &dyn Storage,
+    ctx: &RequestContext,
+    claims: &Claims,
+    bytes: Vec<u8>,
+) -> Result<AvatarResponse> {
+    let user = find_user(db, claims.sub).await?;
+
+    let thumbnails = tokio::task::spawn_blocking(move || avatar::process(&bytes))
+        .await
+        .map_err(|e| AppError::Internal(format!("Avatar processing failed: {}", e)))??;
+
+    let mut stored = Vec::with_capacity(thumbnails.len());
+    for thumbnail in thumbnails {
+        let key = avatar::storage_key(user.id, thumbnail.size);
+        storage.put(&key, thumbnail.bytes).await?;
+        stored.push(AvatarThumbnail {
+            size: thumbnail.size,
+            url: storage.url(&key),
+        });
+    }
+
+    let avatar_url = storage.url(&avatar::storage_key(user.id, avatar::DEFAULT_AVATAR_SIZE));
+
+    let change = EntityChange::capture(&user);
+    let mut active: users::ActiveModel = user.into();
+    active.avatar = Set(Some(avatar_url.clone()));
+    active.updated_at = Set(chrono::Utc::now().into());
+
+    let txn = db.begin().await?;
+    let user = active.update(&txn).await?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.upload_avatar", "user", user.id).change(change.updated(&user)),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    Ok(AvatarResponse {
+        avatar: avatar_url,
+        thumbnails: stored,
+    })
+}
+
+/// Start an email change for the caller
+///
+/// The new address receives a verification token and the current address is
+/// always warned, since an unexpected change signals a takeover attempt; the
+/// email itself stays unchanged until the token is confirmed.
+pub async fn request_email_change(
+    db: &DatabaseConnection,
+    mailer: &dyn Mailer,
+    claims: &Claims,
+    mut req: ChangeEmailRequest,
+) -> Result<EmailChangePending> {
+    let user = find_user(db, claims.sub).await?;
+    req.new_email = normalize_email(&req.new_email)?;
+
+    if !verify_password(&req.password, &user.password)? {
+        return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+    }
+
+    if req.new_email == user.email {
+        return Err(AppError::BadRequest(
+            "New email must differ from the current one".to_string(),
+        ));
+    }
+    ensure_email_available(db, &req.new_email, None).await?;
+
+    let token = generate_token();
+    let expires_at = chrono::Utc::now() + Duration::hours(EMAIL_CHANGE_TOKEN_TTL_HOURS);
+    let user_preferences = preferences::load(db, user.id).await?;
+
+    let txn = db.begin().await?;
+
+    // Only the latest request can be confirmed
+    email_change_requests::Entity::delete_many()
+        .filter(email_change_requests::Column::UserId.eq(user.id))
+        .filter(email_change_requests::Column::ConfirmedAt.is_null())
+        .exec(&txn)
+        .await?;
+
+    email_change_requests::ActiveModel {
+        user_id: Set(user.id),
+        new_email: Set(req.new_email.clone()),
+        token_hash: Set(hash_token(&token)),
+        expires_at: Set(expires_at.into()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    // Send before committing so a delivery failure leaves no dangling request
+    mailer
+        .send(Email::new(
+            &req.new_email,
+            "Confirm your new email address",
+            format!(
+                "Hi {},\n\nUse this token to confirm your new email address:\n\n{}\n\n\
+                 It expires {}. If you did not request this change, ignore this email.",
+                user.nickname,
+                token,
+                user_preferences.format_datetime(expires_at)
+            ),
+        ))
+        .await?;
+
+    txn.commit().await?;
+
+    // The request stands either way; a lost warning must not fail it
+    let notice = Email::new(
+        &user.email,
+        "Email change requested",
+        format!(
+            "Hi {},\n\nA change of your account email to {} was requested. \
+             It takes effect only after the new address is confirmed.\n\n\
+             If this was not you, change your password immediately.",
+            user.nickname, req.new_email
+        ),
+    );
+    if let Err(e) = mailer.send(notice).await {
+        tracing::warn!(
+            "Failed to send email change notice to user {}: {}",
+            user.id,
+            e
+        );
+    }
+
+    Ok(EmailChangePending {
+        new_email: req.new_email,
+        expires_at,
+    })
+}
+
+/// Apply the caller's pending email change once the token is presented
+pub async fn confirm_email_change(
+    db: &DatabaseConnection,
+    ctx: &RequestContext,
+    claims: &Claims,
+    req: ConfirmEmailChangeRequest,
+) -> Result<UserProfile> {
+    let now = chrono::Utc::now();
+
+    let request = email_change_requests::Entity::find()
+        .filter(email_change_requests::Column::TokenHash.eq(hash_token(&req.token)))
+        .filter(email_change_requests::Column::UserId.eq(claims.sub))
+        .filter(email_change_requests::Column::ConfirmedAt.is_null())
+        .one(db)
+        .await?
+        .filter(|request| request.expires_at > now)
+        .ok_or_else(|| AppError::BadRequest("Invalid or expired token".to_string()))?;
+
+    // The address may have been taken since the request was made
+    ensure_email_available(db, &request.new_email, Some(claims.sub)).await?;
+
+    let user = find_user(db, claims.sub).await?;
+    let change = EntityChange::capture(&user);
+    let mut active: users::ActiveModel = user.into();
+    active.email = Set(request.new_email.clone());
+    active.updated_at = Set(now.into());
+
+    let txn = db.begin().await?;
+    let user = active.update(&txn).await.map_err(map_unique_violation)?;
+
+    let mut confirmed: email_change_requests::ActiveModel = request.into();
+    confirmed.confirmed_at = Set(Some(now.into()));
+    confirmed.update(&txn).await?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.change_email", "user", user.id)
+            .change(change.updated(&user))
+            .risk(RiskLevel::Medium),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+
+    build_profile(db, user).await
+}
+
+/// Search hit as returned by the similarity query
+#[derive(Debug, FromQueryResult)]
+struct SearchRow {
+    id: i32,
+    username: String,
+    email: String,
+    nickname: String,
+    avatar: Option<String>,
+    status: UserStatus,
+    score: f32,
+}
+
+/// Fuzzy search over username, email and nickname ranked by trigram similarity
+///
+/// The `<%` operator is served by the trigram GIN indexes, so only candidate
+/// rows are scored even on large tables.
+pub async fn search_users(
+    db: &DatabaseConnection,
+    query: &UserSearchQuery,
+) -> Result<Vec<UserSearchResult>> {
+    let text = query.q.trim();
+    // The length rule on `q` counts surrounding whitespace
+    if text.chars().count() < 2 {
+        return Err(AppError::ValidationError(
+            "Search text must be at least 2 characters".to_string(),
+        ));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+
+    let rows = SearchRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r#"
+        SELECT id, username, email, nickname, avatar, status,
+               GREATEST(
+                   word_similarity($1, username),
+                   word_similarity($1, email),
+                   word_similarity($1, nickname)
+               ) AS score
+        FROM users
+        WHERE deleted_at IS NULL
+          AND ($1 <% username OR $1 <% email OR $1 <% nickname)
+        ORDER BY score DESC, id
+        LIMIT $2
+        "#,
+        [text.into(), (limit as i64).into()],
+    ))
+    .all(db)
+    .await?;
+
+    let terms: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let mut highlights = Vec::new();
+            for (field, value) in [
+                ("username", &row.username),
+                ("email", &row.email),
+                ("nickname", &row.nickname),
+            ] {
+                highlights.extend(find_highlights(field, value, &terms));
+            }
+
+            UserSearchResult {
+                id: row.id,
+                username: row.username,
+                email: row.email,
+                nickname: row.nickname,
+                avatar: row.avatar,
+                status: row.status,
+                score: row.score,
+                highlights,
+            }
+        })
         .collect())
 }
+
+/// Locate case-insensitive occurrences of each term in a field as character ranges
+fn find_highlights(field: &'static str, value: &str, terms: &[String]) -> Vec<SearchHighlight> {
+    let chars: Vec<char> = value.chars().flat_map(char::to_lowercase).collect();
+
+    // Lowercasing can change the length of some characters; skip highlighting then
+    if chars.len() != value.chars().count() {
+        return Vec::new();
+    }
+
+    let mut highlights = Vec::new();
+    for term in terms {
+        let term: Vec<char> = term.chars().collect();
+        if term.is_empty() || term.len() > chars.len() {
+            continue;
+        }
+
+        let mut start = 0;
+        while start + term.len() <= chars.len() {
+            if chars[start..start + term.len()] == term[..] {
+                highlights.push(SearchHighlight {
+                    field,
+                    start,
+                    end: start + term.len(),
+                });
+                start += term.len();
+            } else {
+                start += 1;
+            }
+        }
+    }
+
+    highlights.sort_by_key(|highlight| highlight.start);
+    highlights
+}
+
+/// Apply list sorting to a user query
+pub(super) fn sort_users(
+    select: Select<users::Entity>,
+    query: &UserListQuery,
+) -> Select<users::Entity> {
+    let sort_column = match query.sort_by.unwrap_or_default() {
+        UserSortField::Id => users::Column::Id,
+        UserSortField::Username => users::Column::Username,
+        UserSortField::Email => users::Column::Email,
+        UserSortField::Nickname => users::Column::Nickname,
+        UserSortField::CreatedAt => users::Column::CreatedAt,
+        UserSortField::UpdatedAt => users::Column::UpdatedAt,
+    };
+    let order: Order = query.sort_order.unwrap_or_default().into();
+
+    select
+        .order_by(sort_column, order.clone())
+        // Tie-break on ID so pages stay stable
+        .order_by(users::Column::Id, order)
+}
+
+/// Apply list filters to a user query
+pub(super) fn filter_users(
+    mut select: Select<users::Entity>,
+    query: &UserListQuery,
+) -> Select<users::Entity> {
+    if let Some(status) = query.status {
+        select = select.filter(users::Column::Status.eq(status));
+    }
+
+    if let Some(role_id) = query.role_id {
+        select = select.filter(
+            users::Column::Id.in_subquery(
+                sea_query::Query::select()
+                    .column(user_roles::Column::UserId)
+                    .from(user_roles::Entity)
+                    .and_where(user_roles::Column::RoleId.eq(role_id))
+                    .to_owned(),
+            ),
+        );
+    }
+
+    // Status is the source of truth; `banned_at` only records when the ban started
+    match query.banned {
+        Some(true) => select = select.filter(users::Column::Status.eq(UserStatus::Banned)),
+        Some(false) => select = select.filter(users::Column::Status.ne(UserStatus::Banned)),
+        None => {}
+    }
+
+    if let Some(created_from) = query.created_from {
+        select = select.filter(users::Column::CreatedAt.gte(created_from));
+    }
+
+    if let Some(created_to) = query.created_to {
+        select = select.filter(users::Column::CreatedAt.lt(created_to));
+    }
+
+    if let Some(keyword) = query
+        .keyword
+        .as_deref()
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+    {
+        let pattern = format!("%{}%", escape_like(keyword));
+        let matches = |column: users::Column| {
+            Expr::col((users::Entity, column)).ilike(LikeExpr::new(pattern.clone()).escape('\\'))
+        };
+
+        select = select.filter(
+            Condition::any()
+                .add(matches(users::Column::Username))
+                .add(matches(users::Column::Email))
+                .add(matches(users::Column::Nickname)),
+        );
+    }
+
+    select
+}
+
+/// Escape LIKE wildcards so user input only matches literally
+fn escape_like(input: &str) -> String {
+    input
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Find user by ID or fail with NotFound
+pub(super) async fn find_user<C: ConnectionTrait>(db: &C, user_id: i32) -> Result<users::Model> {
+    users::Entity::find_by_id(user_id)
+        .one(db)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+}
+
+/// Build profile response for a user including their roles
+pub(super) async fn build_profile<C: ConnectionTrait>(
+    db: &C,
+    user: users::Model,
+) -> Result<UserProfile> {
+    let roles = role_service::user_roles(db, user.id).await?;
+    let preferences = preferences::load(db, user.id).await?;
+
+    Ok(UserProfile {
+        id: user.id,
+        username: user.username,
+        email: user.email,
+        nickname: user.nickname,
+        avatar: user.avatar,
+        roles: roles.into_iter().map(Into::into).collect(),
+        status: user.status,
+        deletion_scheduled_at: user.deletion_scheduled_at.map(Into::into),
+        phone: user.phone,
+        sms_mfa_enabled: user.sms_mfa_enabled,
+        preferences,
+    })
+}
+
+/// Fail with Conflict when the username is taken or reserved for another user
+pub(super) async fn ensure_username_available<C: ConnectionTrait>(
+    db: &C,
+    username: &str,
+    except_user_id: Option<i32>,
+) -> Result<()> {
+    let mut select = users::Entity::find().filter(users::Column::Username.eq(username));
+    if let Some(user_id) = except_user_id {
+        select = select.filter(users::Column::Id.ne(user_id));
+    }
+
+    if select.one(db).await?.is_some() {
+        return Err(AppError::Conflict("Username already exists".to_string()));
+    }
+
+    let mut reservations = username_history::Entity::find()
+        .filter(username_history::Column::Username.eq(username))
+        .filter(username_history::Column::ReservedUntil.gt(chrono::Utc::now()));
+    if let Some(user_id) = except_user_id {
+        reservations = reservations.filter(username_history::Column::UserId.ne(user_id));
+    }
+
+    if reservations.one(db).await?.is_some() {
+        return Err(AppError::Conflict("Username is reserved".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Find a user by email, normalizing it first
+pub async fn find_by_email<C: ConnectionTrait>(
+    db: &C,
+    email: &str,
+) -> Result<Option<users::Model>> {
+    let Ok(email) = normalize_email(email) else {
+        return Ok(None);
+    };
+
+    let user = users::Entity::find()
+        .filter(email_matches(&email))
+        .one(db)
+        .await?;
+
+    Ok(user)
+}
+
+/// Fail with Conflict when another user already has the normalized email
+pub(super) async fn ensure_email_available<C: ConnectionTrait>(
+    db: &C,
+    email: &str,
+    except_user_id: Option<i32>,
+) -> Result<()> {
+    let mut select = users::Entity::find().filter(email_matches(email));
+    if let Some(user_id) = except_user_id {
+        select = select.filter(users::Column::Id.ne(user_id));
+    }
+
+    if select.one(db).await?.is_some() {
+        return Err(AppError::Conflict("Email already exists".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Case-insensitive email comparison backed by the `lower(email)` unique index
+fn email_matches(normalized_email: &str) -> SimpleExpr {
+    Expr::expr(Func::lower(Expr::col(users::Column::Email))).eq(normalized_email)
+}
+
+/// Fail with Conflict when another user already has the nickname
+async fn ensure_nickname_available<C: ConnectionTrait>(
+    db: &C,
+    nickname: &str,
+    except_user_id: Option<i32>,
+) -> Result<()> {
+    let mut select = users::Entity::find().filter(users::Column::Nickname.eq(nickname));
+    if let Some(user_id) = except_user_id {
+        select = select.filter(users::Column::Id.ne(user_id));
+    }
+
+    if select.one(db).await?.is_some() {
+        return Err(AppError::Conflict("Nickname already exists".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Fail with BadRequest when any role ID does not exist
+async fn ensure_roles_exist<C: ConnectionTrait>(db: &C, role_ids: &[i32]) -> Result<()> {
+    if role_ids.is_empty() {
+        return Ok(());
+    }
+
+    let found = roles::Entity::find()
+        .filter(roles::Column::Id.is_in(role_ids.iter().copied()))
+        .count(db)
+        .await?;
+
+    let requested: HashSet<i32> = role_ids.iter().copied().collect();
+    if found != requested.len() as u64 {
+        return Err(AppError::BadRequest("Unknown role ID".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Map unique constraint races on users to Conflict instead of a database error
+fn map_unique_violation(e: DbErr) -> AppError {
+    match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            AppError::Conflict("Email or nickname already exists".to_string())
+        }
+        _ => AppError::from(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ranges(value: &str, terms: &[&str]) -> Vec<(usize, usize)> {
+        let terms: Vec<String> = terms.iter().copied().map(String::from).collect();
+        find_highlights("username", value, &terms)
+            .into_iter()
+            .map(|highlight| (highlight.start, highlight.end))
+            .collect()
+    }
+
+    #[test]
+    fn highlights_match_case_insensitively() {
+        assert_eq!(ranges("JohnDoe", &["doe"]), [(4, 7)]);
+    }
+
+    #[test]
+    fn highlights_use_character_offsets() {
+        assert_eq!(ranges("Zoë Smith", &["smith"]), [(4, 9)]);
+    }
+
+    #[test]
+    fn highlights_every_term_in_order() {
+        assert_eq!(ranges("anna_banana", &["ana", "anna"]), [(0, 4), (6, 9)]);
+        assert_eq!(ranges("aaaa", &["aa"]), [(0, 2), (2, 4)]);
+    }
+
+    #[test]
+    fn skips_values_whose_length_changes_when_lowercased() {
+        assert!(ranges("İstanbul", &["stan"]).is_empty());
+        assert!(ranges("john", &["", "johnny"]).is_empty());
+    }
+}
@@ -0,0 +1,230 @@
+use axum::body::Bytes;
+use rust_xlsxwriter::{Format, Workbook, XlsxError};
+use sea_orm::*;
+use std::io::{self, Seek, SeekFrom};
+use tokio::sync::mpsc;
+use tokio_stream::{StreamExt, wrappers::ReceiverStream};
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    common::errors::{AppError, Result},
+    entity::users,
+    modules::{
+        role::service as role_service,
+        user::{
+            dto::UserListQuery,
+            service::{filter_users, sort_users},
+        },
+    },
+};
+
+/// Column headers shared by every export format
+const COLUMNS: [&str; 9] = [
+    "id",
+    "username",
+    "email",
+    "nickname",
+    "roles",
+    "status",
+    "banned_at",
+    "created_at",
+    "updated_at",
+];
+
+/// Users loaded per chunk; bounds memory and batches the role lookups
+const CHUNK_SIZE: usize = 500;
+
+/// Chunks buffered between the database reader and the encoder
+const CHANNEL_CAPACITY: usize = 4;
+
+/// Stream of encoded file chunks for a response body
+pub type ByteStream = std::pin::Pin<Box<dyn tokio_stream::Stream<Item = io::Result<Bytes>> + Send>>;
+
+/// Flattened export row
+struct ExportRow {
+    cells: [String; COLUMNS.len()],
+}
+
+/// Stream users matching the list filters as CSV
+///
+/// Rows are encoded as they are read, so memory use does not grow with the
+/// number of users.
+pub fn csv_stream(db: DatabaseConnection, query: UserListQuery) -> ByteStream {
+    let chunks = spawn_reader(db, query);
+    let (tx, rx) = mpsc::channel::<io::Result<Bytes>>(CHANNEL_CAPACITY);
+
+    tokio::spawn(async move {
+        if tx.send(encode_csv(&[], true)).await.is_err() {
+            return;
+        }
+
+        let mut chunks = ReceiverStream::new(chunks);
+        while let Some(chunk) = chunks.next().await {
+            let bytes = chunk
+                .map_err(|e| io::Error::other(e.to_string()))
+                .and_then(|rows| encode_csv(&rows, false));
+
+            // Stop reading once the client has gone away
+            if tx.send(bytes).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    Box::pin(ReceiverStream::new(rx))
+}
+
+/// Build an XLSX of users matching the list filters and stream it back
+///
+/// XLSX is a zip archive and cannot be emitted incrementally, so the workbook
+/// is written in constant-memory mode to an anonymous temp file first.
+pub async fn xlsx_stream(db: DatabaseConnection, query: UserListQuery) -> Result<ByteStream> {
+    let chunks = spawn_reader(db, query);
+
+    let file = tokio::task::spawn_blocking(move || write_xlsx(chunks))
+        .await
+        .map_err(|e| AppError::Internal(format!("Export task failed: {}", e)))??;
+
+    Ok(Box::pin(ReaderStream::new(tokio::fs::File::from_std(file))))
+}
+
+/// Spawn task reading matching users in chunks with their role names attached
+fn spawn_reader(
+    db: DatabaseConnection,
+    query: UserListQuery,
+) -> mpsc::Receiver<Result<Vec<ExportRow>>> {
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+
+    tokio::spawn(async move {
+        if let Err(e) = read_users(&db, &query, &tx).await {
+            tracing::error!("User export failed: {}", e);
+            let _ = tx.send(Err(e)).await;
+        }
+    });
+
+    rx
+}
+
+/// Read users through a database cursor, sending a chunk every `CHUNK_SIZE` rows
+async fn read_users(
+    db: &DatabaseConnection,
+    query: &UserListQuery,
+    tx: &mpsc::Sender<Result<Vec<ExportRow>>>,
+) -> Result<()> {
+    let select = sort_users(filter_users(users::Entity::find(), query), query);
+    let mut stream = select.stream(db).await?;
+
+    let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+    while let Some(user) = stream.next().await {
+        chunk.push(user?);
+
+        if chunk.len() == CHUNK_SIZE {
+            let rows = with_roles(db, std::mem::take(&mut chunk)).await?;
+            if tx.send(Ok(rows)).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+
+    if !chunk.is_empty() {
+        let rows = with_roles(db, chunk).await?;
+        let _ = tx.send(Ok(rows)).await;
+    }
+
+    Ok(())
+}
+
+/// Flatten a chunk of users, loading role names for the whole chunk at once
+async fn with_roles(db: &DatabaseConnection, users: Vec<users::Model>) -> Result<Vec<ExportRow>> {
+    let user_ids: Vec<i32> = users.iter().map(|user| user.id).collect();
+    let mut roles = role_service::roles_for_users(db, &user_ids).await?;
+
+    Ok(users
+        .into_iter()
+        .map(|user| {
+            let role_names = roles
+                .remove(&user.id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|role| role.name)
+                .collect::<Vec<_>>()
+                .join("; ");
+
+            ExportRow {
+                cells: [
+                    user.id.to_string(),
+                    user.username,
+                    user.email,
+                    user.nickname,
+                    role_names,
+                    user.status.as_str().to_string(),
+                    user.banned_at.map(|at| at.to_rfc3339()).unwrap_or_default(),
+                    user.created_at.to_rfc3339(),
+                    user.updated_at.to_rfc3339(),
+                ],
+            }
+        })
+        .collect())
+}
+
+/// Encode rows as CSV, optionally preceded by the header
+fn encode_csv(rows: &[ExportRow], header: bool) -> io::Result<Bytes> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    if header {
+        writer.write_record(COLUMNS)?;
+    }
+    for row in rows {
+        writer.write_record(row.cells.iter().map(|cell| neutralize_formula(cell)))?;
+    }
+
+    writer
+        .into_inner()
+        .map(Bytes::from)
+        .map_err(|e| io::Error::other(e.to_string()))
+}
+
+/// Prefix cells spreadsheet apps would evaluate as formulas
+fn neutralize_formula(cell: &str) -> String {
+    if cell.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", cell)
+    } else {
+        cell.to_string()
+    }
+}
+
+/// Write every chunk into a constant-memory workbook saved to a temp file
+fn write_xlsx(mut chunks: mpsc::Receiver<Result<Vec<ExportRow>>>) -> Result<std::fs::File> {
+    let xlsx_error = |e: XlsxError| AppError::Internal(format!("Failed to write XLSX: {}", e));
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet_with_constant_memory();
+    worksheet.set_name("Users").map_err(xlsx_error)?;
+
+    let bold = Format::new().set_bold();
+    for (col, name) in COLUMNS.iter().enumerate() {
+        worksheet
+            .write_string_with_format(0, col as u16, *name, &bold)
+            .map_err(xlsx_error)?;
+    }
+
+    let mut row_num: u32 = 1;
+    while let Some(chunk) = chunks.blocking_recv() {
+        for row in chunk? {
+            for (col, cell) in row.cells.iter().enumerate() {
+                worksheet
+                    .write_string(row_num, col as u16, cell)
+                    .map_err(xlsx_error)?;
+            }
+            row_num += 1;
+        }
+    }
+
+    let mut file = tempfile::tempfile()
+        .map_err(|e| AppError::Internal(format!("Failed to create temp file: {}", e)))?;
+    workbook.save_to_writer(&mut file).map_err(xlsx_error)?;
+    file.seek(SeekFrom::Start(0))
+        .map_err(|e| AppError::Internal(format!("Failed to rewind temp file: {}", e)))?;
+
+    Ok(file)
+}
@@ -0,0 +1,292 @@
+use chrono::Duration;
+use sea_orm::*;
+use std::collections::HashSet;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::{
+    common::{
+        RequestContext,
+        errors::{AppError, Result},
+        jwt::Claims,
+        password::verify_password,
+        permission_cache::{Invalidation, PermissionCache},
+        policy::{Action, authorize},
+        validator::{normalize_username, username_skeleton},
+    },
+    entity::{username_history, users},
+    modules::{
+        audit::{
+            change::EntityChange,
+            service::{self as audit, AuditEntry, RiskLevel},
+        },
+        system::service::{
+            self as system_service, RESERVED_USERNAMES_SETTING, UNICODE_USERNAMES_SETTING,
+            USERNAME_CHANGE_LIMIT_SETTING, USERNAME_CHANGE_PERIOD_DAYS_SETTING,
+            USERNAME_RESERVATION_DAYS_SETTING,
+        },
+        user::{
+            dto::{ChangeUsernameRequest, UserProfile},
+            service::{build_profile, ensure_username_available},
+        },
+    },
+};
+
+/// Username changes allowed per period when the setting is missing
+const DEFAULT_CHANGE_LIMIT: u64 = 2;
+
+/// Change period in days when the setting is missing
+const DEFAULT_CHANGE_PERIOD_DAYS: i64 = 30;
+
+/// Reservation in days when the setting is missing
+const DEFAULT_RESERVATION_DAYS: i64 = 90;
+
+/// Reserved names when the setting is missing
+const DEFAULT_RESERVED_USERNAMES: &[&str] =
+    &["admin", "administrator", "root", "support", "system"];
+
+/// Username rules configured in system settings
+pub struct UsernamePolicy {
+    allow_unicode: bool,
+    reserved_skeletons: HashSet<String>,
+}
+
+impl UsernamePolicy {
+    /// Load the Unicode opt-in and reserved names from system settings
+    pub async fn load<C: ConnectionTrait>(db: &C) -> Result<Self> {
+        let allow_unicode = system_service::get_setting(db, UNICODE_USERNAMES_SETTING)
+            .await?
+            .unwrap_or(false);
+        let reserved: Vec<String> = system_service::get_setting(db, RESERVED_USERNAMES_SETTING)
+            .await?
+            .unwrap_or_else(|| {
+                DEFAULT_RESERVED_USERNAMES
+                    .iter()
+                    .map(|&name| String::from(name))
+                    .collect()
+            });
+
+        Ok(Self {
+            allow_unicode,
+            reserved_skeletons: reserved
+                .iter()
+                .map(|name| username_skeleton(name))
+                .collect(),
+        })
+    }
+
+    /// Normalize a username and reject reserved names and their lookalikes
+    ///
+    /// Returns the normalized username together with its confusable skeleton.
+    pub fn check(&self, username: &str) -> Result<(String, String)> {
+        let username = normalize_username(username, self.allow_unicode)?;
+        let skeleton = username_skeleton(&username);
+
+        if self.reserved_skeletons.contains(&skeleton) {
+            return Err(AppError::Conflict("Username is reserved".to_string()));
+        }
+
+        Ok((username, skeleton))
+    }
+}
+
+/// Validate a username for a new or renamed account, returning it normalized with its skeleton
+///
+/// Rejects reserved names, names in use or reserved by another user, and
+/// lookalikes of existing usernames.
+pub(super) async fn prepare_username<C: ConnectionTrait>(
+    db: &C,
+    username: &str,
+    except_user_id: Option<i32>,
+) -> Result<(String, String)> {
+    let (username, skeleton) = UsernamePolicy::load(db).await?.check(username)?;
+
+    ensure_username_available(db, &username, except_user_id).await?;
+
+    let mut lookalikes =
+        users::Entity::find().filter(users::Column::UsernameSkeleton.eq(skeleton.as_str()));
+    if let Some(user_id) = except_user_id {
+        lookalikes = lookalikes.filter(users::Column::Id.ne(user_id));
+    }
+    if lookalikes.one(db).await?.is_some() {
+        return Err(AppError::Conflict(
+            "Username is too similar to an existing one".to_string(),
+        ));
+    }
+
+    Ok((username, skeleton))
+}
+
+/// Rename the caller, keeping the previous username reserved for them
+pub async fn change_username(
+    db: &DatabaseConnection,
+    cache: &PermissionCache,
+    ctx: &RequestContext,
+    claims: &Claims,
+    req: ChangeUsernameRequest,
+) -> Result<UserProfile> {
+    let limit = system_service::get_setting(db, USERNAME_CHANGE_LIMIT_SETTING)
+        .await?
+        .unwrap_or(DEFAULT_CHANGE_LIMIT);
+    let period_days = system_service::get_setting(db, USERNAME_CHANGE_PERIOD_DAYS_SETTING)
+        .await?
+        .unwrap_or(DEFAULT_CHANGE_PERIOD_DAYS);
+    let reservation_days = system_service::get_setting(db, USERNAME_RESERVATION_DAYS_SETTING)
+        .await?
+        .unwrap_or(DEFAULT_RESERVATION_DAYS);
+
+    let now = chrono::Utc::now();
+    let txn = db.begin().await?;
+
+    // Lock the user so concurrent changes cannot both pass the rate limit
+    let user = users::Entity::find_by_id(claims.sub)
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+    authorize(claims, Action::Update, &user)?;
+
+    if !verify_password(&req.password, &user.password)? {
+        return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+    }
+
+    let (username, skeleton) = prepare_username(db, &req.username, Some(user.id)).await?;
+    if username == user.username {
+        return Err(AppError::BadRequest(
+            "New username must differ from the current one".to_string(),
+        ));
+    }
+
+    let recent_changes = username_history::Entity::find()
+        .filter(username_history::Column::UserId.eq(user.id))
+        .filter(username_history::Column::ChangedAt.gt(now - Duration::days(period_days)))
+        .count(&txn)
+        .await?;
+    if recent_changes >= limit {
+        return Err(AppError::Forbidden(format!(
+            "Username can be changed at most {} times every {} days",
+            limit, period_days
+        )));
+    }
+
+    username_history::ActiveModel {
+        user_id: Set(user.id),
+        username: Set(user.username.clone()),
+        changed_at: Set(now.into()),
+        reserved_until: Set((now + Duration::days(reservation_days)).into()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    let change = EntityChange::capture(&user);
+    let mut active: users::ActiveModel = user.into();
+    active.username = Set(username);
+    active.username_skeleton = Set(Some(skeleton));
+    active.updated_at = Set(now.into());
+    let user = active.update(&txn).await.map_err(map_username_violation)?;
+
+    let recorded = audit::record(
+        &txn,
+        ctx,
+        claims,
+        AuditEntry::new("user.change_username", "user", user.id)
+            .change(change.updated(&user))
+            .risk(RiskLevel::Medium),
+    )
+    .await?;
+
+    txn.commit().await?;
+    recorded.attach();
+    cache.invalidate(db, Invalidation::User(user.id)).await?;
+
+    build_profile(db, user).await
+}
+
+/// Find a user by current username, or by a previous one that is still reserved
+pub async fn find_by_username<C: ConnectionTrait>(
+    db: &C,
+    username: &str,
+) -> Result<Option<users::Model>> {
+    // Stored usernames are NFKC-normalized
+    let username: String = username.trim().nfkc().collect();
+
+    let user = users::Entity::find()
+        .filter(users::Column::Username.eq(username.as_str()))
+        .one(db)
+        .await?;
+    if user.is_some() {
+        return Ok(user);
+    }
+
+    let reservation = username_history::Entity::find()
+        .filter(username_history::Column::Username.eq(username.as_str()))
+        .filter(username_history::Column::ReservedUntil.gt(chrono::Utc::now()))
+        .order_by_desc(username_history::Column::ChangedAt)
+        .one(db)
+        .await?;
+
+    match reservation {
+        Some(reservation) => Ok(users::Entity::find_by_id(reservation.user_id)
+            .one(db)
+            .await?),
+        None => Ok(None),
+    }
+}
+
+/// Get a user's profile by current or still-reserved previous username
+pub async fn get_by_username(
+    db: &DatabaseConnection,
+    claims: &Claims,
+    username: &str,
+) -> Result<UserProfile> {
+    let user = find_by_username(db, username)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    authorize(claims, Action::Read, &user)?;
+
+    build_profile(db, user).await
+}
+
+/// Map a rename race on the unique username index to Conflict
+fn map_username_violation(e: DbErr) -> AppError {
+    match e.sql_err() {
+        Some(SqlErr::UniqueConstraintViolation(_)) => {
+            AppError::Conflict("Username already exists".to_string())
+        }
+        _ => AppError::from(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allow_unicode: bool) -> UsernamePolicy {
+        UsernamePolicy {
+            allow_unicode,
+            reserved_skeletons: DEFAULT_RESERVED_USERNAMES
+                .iter()
+                .map(|name| username_skeleton(name))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn rejects_reserved_names_and_lookalikes() {
+        for username in ["admin", "Admin", "ADMIN", "adrnin", "r00t", "SYSTEM"] {
+            assert!(
+                matches!(policy(true).check(username), Err(AppError::Conflict(_))),
+                "accepted {}",
+                username
+            );
+        }
+    }
+
+    #[test]
+    fn returns_normalized_username_with_skeleton() {
+        let (username, skeleton) = policy(false).check(" alice_01 ").unwrap();
+        assert_eq!(username, "alice_01");
+        assert_eq!(skeleton, username_skeleton("alice_01"));
+    }
+}